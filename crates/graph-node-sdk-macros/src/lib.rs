@@ -0,0 +1,282 @@
+//! Procedural macros for `graph-node-sdk`. Use them through the re-exports in
+//! that crate rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Error, FnArg, Ident, ItemFn, Token, Type};
+
+/// Turn a plain `fn(i32) -> i32`, `fn(&[u8]) -> impl NodeOutput` or
+/// `fn(Inputs) -> impl NodeOutput`, or one returning a `Result` of those
+/// with a `NodeError`, into a graph node. `#[graph_node(config)]` also
+/// exports `init` so the node receives its `config`;
+/// `#[graph_node(component)]` turns a `fn(Ports)` into a component node
+/// instead.
+///
+/// See `graph_node_sdk::graph_node` for the generated items.
+#[proc_macro_attribute]
+pub fn graph_node(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr with Punctuated::<Ident, Token![,]>::parse_terminated);
+    let mut component = false;
+    let mut config = false;
+    for arg in &args {
+        let flag = if arg == "component" {
+            &mut component
+        } else if arg == "config" {
+            &mut config
+        } else {
+            return Error::new(
+                arg.span(),
+                "#[graph_node] only takes `component` or `config` as an argument",
+            )
+            .to_compile_error()
+            .into();
+        };
+        *flag = true;
+    }
+    if component && config {
+        return Error::new(
+            args.span(),
+            "component nodes always receive their config through the world's `init`; drop `config`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let function = parse_macro_input!(item as ItemFn);
+    match expand(function, component, config) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(
+    function: ItemFn,
+    component: bool,
+    config: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &function.sig;
+    if sig.ident == "main" {
+        return Err(Error::new(
+            sig.ident.span(),
+            "#[graph_node] generates the `main` export itself; give the node function another name",
+        ));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(Error::new(
+            asyncness.span(),
+            "#[graph_node] functions cannot be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "#[graph_node] functions cannot be generic",
+        ));
+    }
+    let input = match (sig.inputs.len(), sig.inputs.first()) {
+        (1, Some(FnArg::Typed(input))) => input,
+        _ => {
+            return Err(Error::new(
+                sig.inputs.span(),
+                "#[graph_node] functions take exactly one `i32`, `&[u8]` or `Inputs` input",
+            ))
+        }
+    };
+
+    let ident = &sig.ident;
+    if component {
+        return component_exports(&function, &input.ty);
+    }
+    let abi = NodeAbi::of(&input.ty)?;
+    let entry = match abi {
+        NodeAbi::V1 => quote! {
+            #[doc(hidden)]
+            #[no_mangle]
+            pub extern "C" fn main(input: i32) -> i32 {
+                ::graph_node_sdk::abi::main(input, #ident)
+            }
+        },
+        NodeAbi::V2 => v2_exports(quote! { #ident }),
+        NodeAbi::V2Ports => v2_exports(quote! {
+            |bytes: &[u8]| #ident(::graph_node_sdk::Inputs::decode(bytes))
+        }),
+    };
+    // The host writes ABI v2 inputs and the config through `alloc`.
+    let buffers = (config || !matches!(abi, NodeAbi::V1)).then(|| {
+        quote! {
+            #[doc(hidden)]
+            #[no_mangle]
+            pub extern "C" fn alloc(len: usize) -> *mut u8 {
+                ::graph_node_sdk::abi::alloc(len)
+            }
+
+            #[doc(hidden)]
+            #[no_mangle]
+            pub unsafe extern "C" fn dealloc(ptr: *mut u8, len: usize) {
+                ::graph_node_sdk::abi::dealloc(ptr, len)
+            }
+        }
+    });
+    let init = config.then(|| {
+        quote! {
+            #[doc(hidden)]
+            #[no_mangle]
+            pub unsafe extern "C" fn init(ptr: *mut u8, len: usize) {
+                ::graph_node_sdk::config::init(ptr, len)
+            }
+        }
+    });
+
+    let common = common_items();
+    Ok(quote! {
+        #function
+
+        #entry
+
+        #buffers
+
+        #init
+
+        #[doc(hidden)]
+        #[no_mangle]
+        pub extern "C" fn error() -> u64 {
+            ::graph_node_sdk::abi::error()
+        }
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        fn __graph_node_log(level: ::graph_node_sdk::log::Level, message: &str) {
+            ::graph_node_sdk::log::log(level, message)
+        }
+
+        #common
+    })
+}
+
+/// The `init`, `run` and `cabi_realloc` exports of the `graph:node/node`
+/// world for `function`, which takes `Ports`.
+fn component_exports(function: &ItemFn, input: &Type) -> syn::Result<proc_macro2::TokenStream> {
+    let is_ports = matches!(input, Type::Path(path)
+        if path.qself.is_none()
+            && path.path.segments.last().is_some_and(|last| last.ident == "Ports"));
+    if !is_ports {
+        return Err(Error::new(
+            input.span(),
+            "#[graph_node(component)] functions take a single `Ports` input",
+        ));
+    }
+    let ident = &function.sig.ident;
+    let common = common_items();
+    Ok(quote! {
+        #function
+
+        #[doc(hidden)]
+        #[no_mangle]
+        pub unsafe extern "C" fn cabi_realloc(
+            old: *mut u8,
+            old_size: usize,
+            align: usize,
+            size: usize,
+        ) -> *mut u8 {
+            ::graph_node_sdk::component::cabi_realloc(old, old_size, align, size)
+        }
+
+        #[doc(hidden)]
+        #[no_mangle]
+        pub unsafe extern "C" fn init(ptr: *mut u8, len: usize) -> *mut u8 {
+            ::graph_node_sdk::component::init(ptr, len)
+        }
+
+        #[doc(hidden)]
+        #[no_mangle]
+        pub unsafe extern "C" fn run(ptr: *mut u8, len: usize) -> *mut u8 {
+            ::graph_node_sdk::component::run(ptr, len, #ident)
+        }
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        fn __graph_node_log(level: ::graph_node_sdk::log::Level, message: &str) {
+            ::graph_node_sdk::component::log(level, message)
+        }
+
+        #common
+    })
+}
+
+/// The `_start` stub and panic handler every node gets.
+fn common_items() -> proc_macro2::TokenStream {
+    quote! {
+        /// Minimal entry point so the module links without the standard
+        /// `fn main()` expectation. The runtime never calls this.
+        #[doc(hidden)]
+        #[no_mangle]
+        pub extern "C" fn _start() {}
+
+        #[cfg(target_arch = "wasm32")]
+        #[panic_handler]
+        fn __graph_node_panic(_info: &::core::panic::PanicInfo) -> ! {
+            ::core::arch::wasm32::unreachable()
+        }
+    }
+}
+
+/// The ABI v2 `run` export, forwarding to `node`, a `fn(&[u8]) -> impl
+/// NodeResult` expression.
+fn v2_exports(node: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        #[doc(hidden)]
+        #[no_mangle]
+        pub unsafe extern "C" fn run(ptr: *mut u8, len: usize) -> u64 {
+            ::graph_node_sdk::abi::run(ptr, len, #node)
+        }
+    }
+}
+
+/// The node ABI selected by the annotated function's input type.
+enum NodeAbi {
+    /// `i32` input, exported as `main`.
+    V1,
+    /// `&[u8]` input, exported as `run`.
+    V2,
+    /// `Inputs` input: ABI v2 carrying a JSON port envelope.
+    V2Ports,
+}
+
+impl NodeAbi {
+    fn of(ty: &Type) -> syn::Result<Self> {
+        match ty {
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("i32") => {
+                Ok(NodeAbi::V1)
+            }
+            Type::Path(path)
+                if path.qself.is_none()
+                    && path
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|last| last.ident == "Inputs") =>
+            {
+                Ok(NodeAbi::V2Ports)
+            }
+            Type::Reference(reference) if reference.mutability.is_none() => {
+                match &*reference.elem {
+                    Type::Slice(slice) if is_u8(&slice.elem) => Ok(NodeAbi::V2),
+                    _ => Err(unsupported(ty)),
+                }
+            }
+            _ => Err(unsupported(ty)),
+        }
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("u8"))
+}
+
+fn unsupported(ty: &Type) -> Error {
+    Error::new(
+        ty.span(),
+        "#[graph_node] inputs must be `i32` (ABI v1), `&[u8]` or `Inputs` (ABI v2)",
+    )
+}
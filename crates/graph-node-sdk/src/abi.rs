@@ -0,0 +1,163 @@
+//! Guest side of the node ABIs.
+//!
+//! In ABI v2 the host allocates the input buffer through [`alloc`], copies
+//! the payload in and calls `run`, which takes ownership of that buffer.
+//! `run` returns the output buffer packed as `ptr << 32 | len`; the host
+//! copies it out and hands it back through [`dealloc`].
+//!
+//! Under either ABI a node reports failure through the `error` export: after
+//! `main` or `run` returns, the host calls `error()`, which returns `0` on
+//! success or the packed error record (a little-endian `i32` code followed
+//! by a UTF-8 message). The host ignores the node's output in that case and
+//! releases the record through `dealloc` when the node exports it.
+//!
+//! The `#[graph_node]` attribute wires these functions to the `main` or
+//! `run` exports and `error`, plus `alloc` and `dealloc` for ABI v2 nodes
+//! and for nodes taking the `init` export described in
+//! [`config`](crate::config).
+
+use alloc::alloc::Layout;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+
+use crate::error::NodeError;
+use crate::lock::SpinLock;
+
+/// Values a byte node may return.
+pub trait NodeOutput {
+    fn into_bytes(self) -> Vec<u8>;
+}
+
+impl NodeOutput for Vec<u8> {
+    fn into_bytes(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl NodeOutput for String {
+    fn into_bytes(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+impl NodeOutput for &str {
+    fn into_bytes(self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl NodeOutput for &[u8] {
+    fn into_bytes(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+/// Values a node function may return: its output, or a [`Result`] whose
+/// error is reported to the host instead of the output.
+pub trait NodeResult<T> {
+    fn into_result(self) -> Result<T, NodeError>;
+}
+
+impl NodeResult<i32> for i32 {
+    fn into_result(self) -> Result<i32, NodeError> {
+        Ok(self)
+    }
+}
+
+impl NodeResult<i32> for Result<i32, NodeError> {
+    fn into_result(self) -> Result<i32, NodeError> {
+        self
+    }
+}
+
+impl<O: NodeOutput> NodeResult<Vec<u8>> for O {
+    fn into_result(self) -> Result<Vec<u8>, NodeError> {
+        Ok(self.into_bytes())
+    }
+}
+
+impl<O: NodeOutput> NodeResult<Vec<u8>> for Result<O, NodeError> {
+    fn into_result(self) -> Result<Vec<u8>, NodeError> {
+        self.map(NodeOutput::into_bytes)
+    }
+}
+
+/// The error record of the current execution, waiting for the host.
+static PENDING_ERROR: SpinLock<Option<Box<[u8]>>> = SpinLock::new(None);
+
+pub(crate) fn report(error: NodeError) {
+    let record = error.encode().into_boxed_slice();
+    PENDING_ERROR.with(|pending| *pending = Some(record));
+}
+
+/// Hand the pending error record to the host, packed like a `run` result,
+/// or return `0` when the node succeeded.
+pub fn error() -> u64 {
+    match PENDING_ERROR.with(Option::take) {
+        Some(record) => {
+            let len = record.len();
+            pack(Box::into_raw(record).cast::<u8>(), len)
+        }
+        None => 0,
+    }
+}
+
+/// Run an ABI v1 node, reporting an error result through [`error`] and
+/// returning `0` in its place.
+pub fn main<R: NodeResult<i32>>(input: i32, node: fn(i32) -> R) -> i32 {
+    node(input).into_result().unwrap_or_else(|error| {
+        report(error);
+        0
+    })
+}
+
+/// Pack a buffer into the `run` return value.
+pub fn pack(ptr: *const u8, len: usize) -> u64 {
+    ((ptr as usize as u64) << 32) | len as u64
+}
+
+/// Allocate a `len`-byte buffer for the host to write into.
+pub fn alloc(len: usize) -> *mut u8 {
+    if len == 0 {
+        return NonNull::dangling().as_ptr();
+    }
+    // SAFETY: the layout has a non-zero size.
+    unsafe { alloc::alloc::alloc(Layout::array::<u8>(len).unwrap()) }
+}
+
+/// Release a buffer previously returned by [`alloc`] or [`run`].
+///
+/// # Safety
+///
+/// `ptr` and `len` must describe a live buffer handed out by this module.
+pub unsafe fn dealloc(ptr: *mut u8, len: usize) {
+    if len != 0 {
+        alloc::alloc::dealloc(ptr, Layout::array::<u8>(len).unwrap());
+    }
+}
+
+/// Run `node` over the host-provided input buffer and return the packed
+/// output buffer. The input buffer is released before returning. An error
+/// result is reported through [`error`] and yields an empty output.
+///
+/// # Safety
+///
+/// `ptr` and `len` must describe a buffer obtained from [`alloc`] that the
+/// host has fully initialised.
+pub unsafe fn run<R: NodeResult<Vec<u8>>>(ptr: *mut u8, len: usize, node: fn(&[u8]) -> R) -> u64 {
+    let input: Box<[u8]> = if len == 0 {
+        Box::default()
+    } else {
+        Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len))
+    };
+    let output = node(&input).into_result().unwrap_or_else(|error| {
+        report(error);
+        Vec::new()
+    });
+    drop(input);
+    let output = output.into_boxed_slice();
+    let len = output.len();
+    pack(Box::into_raw(output).cast::<u8>(), len)
+}
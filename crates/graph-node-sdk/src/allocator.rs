@@ -0,0 +1,72 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::wasm32;
+use core::cell::Cell;
+use core::ptr;
+
+const PAGE_SIZE: usize = 65536;
+
+/// A bump allocator over freshly grown linear memory.
+///
+/// Node instances are created for a single execution and then discarded, so
+/// memory is never reclaimed: `dealloc` is a no-op. The SDK installs it as
+/// the `#[global_allocator]` of every node.
+pub struct BumpAllocator {
+    next: Cell<usize>,
+    end: Cell<usize>,
+}
+
+// SAFETY: wasm32 nodes are single-threaded.
+unsafe impl Sync for BumpAllocator {}
+
+impl BumpAllocator {
+    pub const fn new() -> Self {
+        Self {
+            next: Cell::new(0),
+            end: Cell::new(0),
+        }
+    }
+}
+
+impl Default for BumpAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for BumpAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let align = layout.align();
+        let size = layout.size();
+        let mut start = self.next.get().next_multiple_of(align);
+        // `next` is 0 until the first allocation claims a region.
+        if self.next.get() == 0
+            || start
+                .checked_add(size)
+                .is_none_or(|end| end > self.end.get())
+        {
+            let Some(bytes) = size.checked_add(align) else {
+                return ptr::null_mut();
+            };
+            let pages = bytes.div_ceil(PAGE_SIZE);
+            let previous = wasm32::memory_grow(0, pages);
+            if previous == usize::MAX {
+                return ptr::null_mut();
+            }
+            let base = previous * PAGE_SIZE;
+            // Keep using the tail of the current region when the new pages
+            // extend it; otherwise start over at the fresh pages. A module
+            // without initial memory grows its first region at address 0,
+            // which Rust reads as null, so that region starts one byte in;
+            // the extra `align` bytes grown above leave room for it.
+            if self.next.get() == 0 || base != self.end.get() {
+                self.next.set(base.max(1));
+            }
+            self.end.set(base + pages * PAGE_SIZE);
+            start = self.next.get().next_multiple_of(align);
+        }
+        self.next.set(start + size);
+        start as *mut u8
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
@@ -0,0 +1,312 @@
+//! Guest side of the component node ABI.
+//!
+//! A `#[graph_node(component)]` function implements the `graph:node/node`
+//! WIT world (`crates/graph-runtime/wit/node.wit`) instead of a core ABI.
+//! The crate still compiles to a core module; the attribute generates the
+//! world's exports with the canonical ABI, and `graph build` wraps the
+//! artifact into a component.
+//!
+//! The canonical ABI lays the world's types out in linear memory as
+//! follows; every record is 4-aligned:
+//!
+//! * `string` and `list<T>` are a pointer followed by a length;
+//! * `payload` is a discriminant byte (`0` for `int`, `1` for `bytes`) and
+//!   the case's value at offset 4, 12 bytes in all;
+//! * `port` is its name and then its payload at offset 8, 20 bytes in all;
+//! * `result<T, node-error>` is a discriminant byte (`0` for ok) and then
+//!   `T` or the error's code and message at offset 4.
+//!
+//! The host writes arguments through `cabi_realloc` and hands them over to
+//! the node; results stay in node memory until the instance is dropped, as
+//! the bump allocator never reclaims memory anyway.
+
+use alloc::alloc::Layout;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+
+use serde_json::Value;
+
+use crate::config;
+use crate::error::NodeError;
+use crate::log::Level;
+
+/// A value on a port: an integer, or bytes holding text, JSON or binary
+/// data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    Int(i32),
+    Bytes(Vec<u8>),
+}
+
+impl From<i32> for Payload {
+    fn from(value: i32) -> Self {
+        Payload::Int(value)
+    }
+}
+
+impl From<&str> for Payload {
+    fn from(text: &str) -> Self {
+        Payload::Bytes(text.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Payload {
+    fn from(text: String) -> Self {
+        Payload::Bytes(text.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(bytes: Vec<u8>) -> Self {
+        Payload::Bytes(bytes)
+    }
+}
+
+impl From<&Value> for Payload {
+    fn from(value: &Value) -> Self {
+        Payload::Bytes(serde_json::to_vec(value).unwrap_or_default())
+    }
+}
+
+/// Named port values, passed to and returned from a component node.
+///
+/// The host converts each input to its declared port type first, so an
+/// `i32` port always holds [`Payload::Int`] and `string`, `json` and
+/// `bytes` ports hold [`Payload::Bytes`]. A node without declared ports
+/// receives its upstream value as `in` and returns its output as `out`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ports(Vec<(String, Payload)>);
+
+impl Ports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the port is present.
+    pub fn contains(&self, port: &str) -> bool {
+        self.get(port).is_some()
+    }
+
+    /// The value of a port.
+    pub fn get(&self, port: &str) -> Option<&Payload> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == port)
+            .map(|(_, value)| value)
+    }
+
+    /// The value of an `i32` port.
+    pub fn i32(&self, port: &str) -> Option<i32> {
+        match self.get(port)? {
+            Payload::Int(value) => Some(*value),
+            Payload::Bytes(_) => None,
+        }
+    }
+
+    /// The value of a `bytes` port, or the raw bytes of a `string` or `json`
+    /// port.
+    pub fn bytes(&self, port: &str) -> Option<&[u8]> {
+        match self.get(port)? {
+            Payload::Int(_) => None,
+            Payload::Bytes(bytes) => Some(bytes),
+        }
+    }
+
+    /// The value of a `string` port.
+    pub fn str(&self, port: &str) -> Option<&str> {
+        core::str::from_utf8(self.bytes(port)?).ok()
+    }
+
+    /// The value of a `json` port.
+    pub fn json(&self, port: &str) -> Option<Value> {
+        serde_json::from_slice(self.bytes(port)?).ok()
+    }
+
+    /// Set a port, replacing any previous value.
+    pub fn set(mut self, port: &str, value: impl Into<Payload>) -> Self {
+        let value = value.into();
+        match self.0.iter_mut().find(|(name, _)| name == port) {
+            Some((_, slot)) => *slot = value,
+            None => self.0.push((port.into(), value)),
+        }
+        self
+    }
+}
+
+/// Values a component node function may return: its output ports, or a
+/// [`Result`] whose error is reported to the host instead.
+pub trait ComponentResult {
+    fn into_result(self) -> Result<Ports, NodeError>;
+}
+
+impl ComponentResult for Ports {
+    fn into_result(self) -> Result<Ports, NodeError> {
+        Ok(self)
+    }
+}
+
+impl ComponentResult for Result<Ports, NodeError> {
+    fn into_result(self) -> Result<Ports, NodeError> {
+        self
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "$root")]
+extern "C" {
+    #[link_name = "log"]
+    fn host_log(level: i32, ptr: *const u8, len: usize);
+}
+
+/// Send one line to the host through the world's `log` import.
+pub fn log(level: Level, message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    // SAFETY: the host only reads `len` bytes at `ptr` during the call.
+    unsafe {
+        host_log(level as i32, message.as_ptr(), message.len())
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = (level, message);
+}
+
+/// The canonical ABI allocator, through which the host writes arguments.
+///
+/// # Safety
+///
+/// A non-null `old` must be a live allocation of `old_size` bytes aligned to
+/// `align`, handed out by this function.
+pub unsafe fn cabi_realloc(old: *mut u8, old_size: usize, align: usize, size: usize) -> *mut u8 {
+    if size == 0 {
+        return align as *mut u8;
+    }
+    let layout = Layout::from_size_align(size, align).unwrap();
+    if old.is_null() {
+        alloc::alloc::alloc(layout)
+    } else {
+        let old_layout = Layout::from_size_align(old_size, align).unwrap();
+        alloc::alloc::realloc(old, old_layout, size)
+    }
+}
+
+/// The world's `init` export: remember the configuration for
+/// [`config`](crate::config()) and return a pointer to the
+/// `result<_, node-error>`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must describe a UTF-8 string the host wrote through
+/// [`cabi_realloc`].
+pub unsafe fn init(ptr: *mut u8, len: usize) -> *mut u8 {
+    let result = config::set(&take_bytes(ptr, len));
+    write_result(result, |_, ()| {})
+}
+
+/// The world's `run` export: lift the input ports, run `node` on them and
+/// return a pointer to the lowered `result<list<port>, node-error>`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must describe a `list<port>` the host wrote through
+/// [`cabi_realloc`].
+pub unsafe fn run<R: ComponentResult>(ptr: *mut u8, len: usize, node: fn(Ports) -> R) -> *mut u8 {
+    let inputs = (0..len)
+        .map(|index| {
+            let port = ptr.add(index * PORT_SIZE);
+            let name = take_string(load(port, 0) as *mut u8, load(port, 4));
+            let value = match *port.add(8) {
+                0 => Payload::Int(load(port, 12) as i32),
+                _ => Payload::Bytes(take_bytes(load(port, 12) as *mut u8, load(port, 16))),
+            };
+            (name, value)
+        })
+        .collect();
+    let outputs = node(Ports(inputs)).into_result();
+    write_result(outputs, |area, outputs| {
+        let len = outputs.0.len();
+        let ports = leak(len * PORT_SIZE);
+        for (index, (name, value)) in outputs.0.into_iter().enumerate() {
+            let port = ports.add(index * PORT_SIZE);
+            store_bytes(port, 0, name.into_bytes());
+            match value {
+                Payload::Int(value) => {
+                    *port.add(8) = 0;
+                    port.add(12).cast::<i32>().write(value);
+                }
+                Payload::Bytes(bytes) => {
+                    *port.add(8) = 1;
+                    store_bytes(port, 12, bytes);
+                }
+            }
+        }
+        store(area, 4, ports as usize);
+        store(area, 8, len);
+    })
+}
+
+/// Size of a lowered `port`.
+const PORT_SIZE: usize = 20;
+
+/// Size of a lowered `result<T, node-error>` whose ok case fits in 8 bytes.
+const RESULT_SIZE: usize = 16;
+
+/// Lower `result` into a fresh return area, writing the ok case with
+/// `write_ok`, and return the area.
+unsafe fn write_result<T>(
+    result: Result<T, NodeError>,
+    write_ok: impl FnOnce(*mut u8, T),
+) -> *mut u8 {
+    let area = leak(RESULT_SIZE);
+    match result {
+        Ok(value) => {
+            *area = 0;
+            write_ok(area, value);
+        }
+        Err(error) => {
+            *area = 1;
+            area.add(4).cast::<i32>().write(error.code);
+            store_bytes(area, 8, error.message.into_bytes());
+        }
+    }
+    area
+}
+
+/// A 4-aligned buffer of `size` bytes that stays allocated for the rest of
+/// the instance's life.
+fn leak(size: usize) -> *mut u8 {
+    if size == 0 {
+        return NonNull::<u32>::dangling().as_ptr().cast();
+    }
+    // SAFETY: the layout has a non-zero size.
+    unsafe { alloc::alloc::alloc(Layout::from_size_align(size, 4).unwrap()) }
+}
+
+unsafe fn load(base: *mut u8, offset: usize) -> usize {
+    base.add(offset).cast::<u32>().read() as usize
+}
+
+unsafe fn store(base: *mut u8, offset: usize, value: usize) {
+    base.add(offset).cast::<u32>().write(value as u32);
+}
+
+/// Store `bytes` as the pointer and length at `offset`, leaking them.
+unsafe fn store_bytes(base: *mut u8, offset: usize, bytes: Vec<u8>) {
+    let len = bytes.len();
+    store(base, offset, bytes.leak().as_mut_ptr() as usize);
+    store(base, offset + 4, len);
+}
+
+/// Take ownership of a byte buffer the host allocated with
+/// [`cabi_realloc`] at alignment 1.
+unsafe fn take_bytes(ptr: *mut u8, len: usize) -> Vec<u8> {
+    if len == 0 {
+        Vec::new()
+    } else {
+        Vec::from_raw_parts(ptr, len, len)
+    }
+}
+
+/// Like [`take_bytes`], for a string the host has already checked is UTF-8.
+unsafe fn take_string(ptr: *mut u8, len: usize) -> String {
+    String::from_utf8_unchecked(take_bytes(ptr, len))
+}
@@ -0,0 +1,96 @@
+//! A node's static configuration.
+//!
+//! The `config` object of a node in `graph.json` is serialized by the host
+//! and handed to the node once, before its entry point runs: through the
+//! `init(ptr, len)` export that `#[graph_node(config)]` generates for core
+//! modules, with the buffer allocated through `alloc` like an ABI v2 input,
+//! and through the world's `init` for components. The host skips a core
+//! module's `init` when the node has no `config`; a component receives
+//! `null`. Read it with [`config`]:
+//!
+//! ```ignore
+//! let config = graph_node_sdk::config();
+//! let modulus = config.i32("modulus").unwrap_or(5);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+
+use serde_json::{Map, Value};
+
+use crate::error::NodeError;
+use crate::lock::SpinLock;
+
+/// The configuration a node received.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config(Map<String, Value>);
+
+impl Config {
+    /// Whether the key is set.
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// The raw JSON value of a key.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// The value of an integer key, if it fits an `i32`.
+    pub fn i32(&self, key: &str) -> Option<i32> {
+        self.get(key)?.as_i64()?.try_into().ok()
+    }
+
+    /// The value of a number key.
+    pub fn f64(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_f64()
+    }
+
+    /// The value of a boolean key.
+    pub fn bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+
+    /// The value of a string key.
+    pub fn str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+}
+
+static CONFIG: SpinLock<Option<Config>> = SpinLock::new(None);
+
+/// The node's configuration; empty when the graph gives it none.
+pub fn config() -> Config {
+    CONFIG.with(|config| config.clone()).unwrap_or_default()
+}
+
+/// Remember the configuration the host passed as JSON text: an object, or
+/// `null` for none.
+pub(crate) fn set(json: &[u8]) -> Result<(), NodeError> {
+    let config = match serde_json::from_slice(json) {
+        Ok(Value::Object(config)) => config,
+        Ok(Value::Null) => Map::new(),
+        _ => return Err(NodeError::new(-1, "config is not a JSON object")),
+    };
+    CONFIG.with(|stored| *stored = Some(Config(config)));
+    Ok(())
+}
+
+/// The core modules' `init` export: take the host-provided config buffer
+/// and remember it, reporting a malformed config through
+/// [`abi::error`](crate::abi::error).
+///
+/// # Safety
+///
+/// `ptr` and `len` must describe a buffer obtained from
+/// [`abi::alloc`](crate::abi::alloc) that the host has fully initialised.
+pub unsafe fn init(ptr: *mut u8, len: usize) {
+    let json: Box<[u8]> = if len == 0 {
+        Box::default()
+    } else {
+        Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len))
+    };
+    if let Err(error) = set(&json) {
+        crate::abi::report(error);
+    }
+}
@@ -0,0 +1,39 @@
+//! Errors a node reports to the host instead of an output.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A failure the node detected itself, such as an unknown user or an
+/// invalid input. Return it as the error of a `Result` from a
+/// `#[graph_node]` function; the host records it separately from traps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeError {
+    /// Application-defined code, e.g. `404` for a missing record.
+    pub code: i32,
+    pub message: String,
+}
+
+impl NodeError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The error record read by the host: the code as a little-endian
+    /// `i32`, then the UTF-8 message.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut record = Vec::with_capacity(4 + self.message.len());
+        record.extend_from_slice(&self.code.to_le_bytes());
+        record.extend_from_slice(self.message.as_bytes());
+        record
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error {}: {}", self.code, self.message)
+    }
+}
@@ -0,0 +1,179 @@
+//! Guest-side SDK for writing graph runtime nodes in Rust.
+//!
+//! A node is a `no_std` `cdylib` package in the `nodes` workspace whose
+//! `src/lib.rs` holds one annotated function. Its manifest depends on this
+//! crate and names the node id it provides:
+//!
+//! ```toml
+//! [lib]
+//! crate-type = ["cdylib"]
+//!
+//! [dependencies]
+//! graph-node-sdk.workspace = true
+//!
+//! [package.metadata.graph]
+//! node = "double"
+//! ```
+//!
+//! ```ignore
+//! #![no_std]
+//!
+//! use graph_node_sdk::graph_node;
+//!
+//! #[graph_node]
+//! fn double(input: i32) -> i32 {
+//!     input * 2
+//! }
+//! ```
+//!
+//! Nodes that exchange strings, JSON or binary blobs take a byte slice
+//! instead and speak ABI v2:
+//!
+//! ```ignore
+//! #![no_std]
+//!
+//! use graph_node_sdk::{graph_node, String};
+//!
+//! #[graph_node]
+//! fn greet(name: &[u8]) -> String {
+//!     let name = core::str::from_utf8(name).unwrap_or("stranger");
+//!     graph_node_sdk::format!("Hello, {name}!")
+//! }
+//! ```
+//!
+//! Nodes that declare named ports in `graph.json` take [`Inputs`] and return
+//! [`Outputs`]; they also speak ABI v2, with the ports carried as a JSON
+//! object:
+//!
+//! ```ignore
+//! #![no_std]
+//!
+//! use graph_node_sdk::{graph_node, Inputs, Outputs};
+//!
+//! #[graph_node]
+//! fn add(inputs: Inputs) -> Outputs {
+//!     let sum = inputs.i32("a").unwrap_or(0) + inputs.i32("b").unwrap_or(0);
+//!     Outputs::new().set("sum", sum)
+//! }
+//! ```
+//!
+//! A node that can fail without trapping returns a `Result` whose error is a
+//! [`NodeError`]; the host records the code and message as the node's
+//! failure and does not pass an output downstream:
+//!
+//! ```ignore
+//! #![no_std]
+//!
+//! use graph_node_sdk::{graph_node, NodeError};
+//!
+//! #[graph_node]
+//! fn fetch_user(id: i32) -> Result<i32, NodeError> {
+//!     match id {
+//!         0 => Err(NodeError::new(404, "user not found")),
+//!         id => Ok(id + 1000),
+//!     }
+//! }
+//! ```
+//!
+//! A node may instead implement the `graph:node/node` WIT world as a
+//! WebAssembly component. Such a node takes and returns [`Ports`], typed by
+//! the host:
+//!
+//! ```ignore
+//! #![no_std]
+//!
+//! use graph_node_sdk::{graph_node, NodeError, Ports};
+//!
+//! #[graph_node(component)]
+//! fn double(inputs: Ports) -> Result<Ports, NodeError> {
+//!     let value = inputs.i32("in").ok_or(NodeError::new(400, "no input"))?;
+//!     Ok(Ports::new().set("out", value * 2))
+//! }
+//! ```
+//!
+//! A node annotated `#[graph_node(config)]`, and every component node, can
+//! read the `config` object given to it in `graph.json` through
+//! [`config()`], so the same module can be reused with different
+//! parameters:
+//!
+//! ```ignore
+//! #[graph_node(config)]
+//! fn scale(input: i32) -> i32 {
+//!     input * graph_node_sdk::config().i32("factor").unwrap_or(1)
+//! }
+//! ```
+//!
+//! [`node_log!`] sends diagnostics to the host, which prints them prefixed
+//! with the node id and keeps them in the node's execution record. The node
+//! must be allowed the `log` capability in its `sandbox` policy.
+//!
+//! `graph build` (which `scripts/build-wasm.sh` runs) compiles the packages
+//! for `wasm32-wasip1`, wraps component nodes into components, copies each
+//! artifact to `nodes/<id>.wasm` and records it in
+//! `nodes/build-manifest.json`; list the package in the workspace's
+//! `members` for it to be built.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod abi;
+#[cfg(target_arch = "wasm32")]
+mod allocator;
+pub mod component;
+pub mod config;
+mod error;
+mod lock;
+pub mod log;
+pub mod ports;
+
+pub use abi::{NodeOutput, NodeResult};
+pub use alloc::{format, string::String, vec, vec::Vec};
+#[cfg(target_arch = "wasm32")]
+pub use allocator::BumpAllocator;
+pub use component::{Payload, Ports};
+pub use config::{config, Config};
+pub use error::NodeError;
+pub use ports::{Inputs, Outputs};
+pub use serde_json::{json, Value};
+
+/// Every node links the SDK, so it provides the allocator for all of them.
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOCATOR: BumpAllocator = BumpAllocator::new();
+
+/// Export a plain function as the node entry point.
+///
+/// The annotated function is kept as written. Its signature selects the
+/// ABI:
+///
+/// * `fn(i32) -> i32` speaks ABI v1: the attribute generates the
+///   `#[no_mangle] extern "C" fn main(i32) -> i32` export the host calls.
+/// * `fn(&[u8]) -> impl NodeOutput` speaks ABI v2: the attribute generates
+///   the `run` export described in [`abi`].
+/// * `fn(Inputs) -> impl NodeOutput` also speaks ABI v2, decoding the port
+///   envelope into [`Inputs`] first; return [`Outputs`] to fill the
+///   declared output ports.
+///
+/// Any of these may return `Result<_, NodeError>` instead; the attribute
+/// always generates the `error` export through which the host collects a
+/// reported error. ABI v2 nodes also get the `alloc` and `dealloc` exports
+/// the host moves buffers through.
+///
+/// `#[graph_node(config)]` additionally generates the `init` export through
+/// which the host passes the node's [`config`], and `alloc` and `dealloc`
+/// for it. Without it, [`config()`] is always empty and the host refuses to
+/// run the node with a `config`.
+///
+/// `#[graph_node(component)]` takes `fn(Ports) -> Ports` or
+/// `fn(Ports) -> Result<Ports, NodeError>` and generates the `init`, `run`
+/// and `cabi_realloc` exports of the `graph:node/node` world described in
+/// [`component`] instead.
+///
+/// In every case it also generates an empty `_start` stub so the module
+/// links without a standard `fn main()`, and a panic handler that traps
+/// with `unreachable`, so a panicking node fails its execution instead of
+/// hanging the runtime.
+///
+/// A node crate must contain exactly one `#[graph_node]` function.
+pub use graph_node_sdk_macros::graph_node;
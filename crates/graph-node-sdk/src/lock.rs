@@ -0,0 +1,51 @@
+//! A minimal lock for the SDK's statics.
+//!
+//! A node instance runs on one thread, but the SDK also compiles for host
+//! targets, where nothing stops two threads from touching the same static.
+//! `no_std` has no `Mutex`, so the statics sit behind this spin lock, which
+//! costs a couple of atomic operations when uncontended.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub(crate) struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value is only reached through `with`, which holds the lock, so
+// at most one thread accesses it at a time.
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Run `f` with exclusive access to the value. `f` must not lock the same
+    /// lock again.
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let _guard = Unlock(&self.locked);
+        // SAFETY: holding the lock makes this the only reference.
+        f(unsafe { &mut *self.value.get() })
+    }
+}
+
+/// Releases the lock when dropped, even if `f` panics.
+struct Unlock<'a>(&'a AtomicBool);
+
+impl Drop for Unlock<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
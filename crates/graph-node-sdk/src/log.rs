@@ -0,0 +1,74 @@
+//! Logging through the host's `graph.log` import.
+//!
+//! Use the [`node_log!`](crate::node_log) macro rather than calling [`log`]
+//! directly. The host only links the import for nodes whose sandbox policy
+//! allows the `log` capability; other nodes fail to instantiate once they
+//! log anything.
+//!
+//! Component nodes log through the world's `log` import instead
+//! ([`component::log`](crate::component::log)). `node_log!` calls the
+//! `__graph_node_log` function `#[graph_node]` generates at the node crate's
+//! root, which forwards to the import matching the node's ABI, so a node
+//! that never logs imports neither.
+
+/// Severity of a log line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(i32)]
+pub enum Level {
+    Trace = 0,
+    Debug = 1,
+    Info = 2,
+    Warn = 3,
+    Error = 4,
+}
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "graph")]
+extern "C" {
+    #[link_name = "log"]
+    fn host_log(level: i32, ptr: *const u8, len: usize);
+}
+
+/// Send one line to the host.
+pub fn log(level: Level, message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    // SAFETY: the host only reads `len` bytes at `ptr` during the call.
+    unsafe {
+        host_log(level as i32, message.as_ptr(), message.len())
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = (level, message);
+}
+
+/// Log a formatted line through the host, at `info` level unless a level
+/// (`trace`, `debug`, `info`, `warn` or `error`) comes first:
+///
+/// ```ignore
+/// node_log!("computing discount for {}", user);
+/// node_log!(warn, "discount {} looks too high", discount);
+/// ```
+///
+/// It can only be used in a crate with a `#[graph_node]` function at its
+/// root.
+#[macro_export]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! node_log {
+    (trace, $($arg:tt)+) => {
+        crate::__graph_node_log($crate::log::Level::Trace, &$crate::format!($($arg)+))
+    };
+    (debug, $($arg:tt)+) => {
+        crate::__graph_node_log($crate::log::Level::Debug, &$crate::format!($($arg)+))
+    };
+    (info, $($arg:tt)+) => {
+        crate::__graph_node_log($crate::log::Level::Info, &$crate::format!($($arg)+))
+    };
+    (warn, $($arg:tt)+) => {
+        crate::__graph_node_log($crate::log::Level::Warn, &$crate::format!($($arg)+))
+    };
+    (error, $($arg:tt)+) => {
+        crate::__graph_node_log($crate::log::Level::Error, &$crate::format!($($arg)+))
+    };
+    ($($arg:tt)+) => {
+        crate::__graph_node_log($crate::log::Level::Info, &$crate::format!($($arg)+))
+    };
+}
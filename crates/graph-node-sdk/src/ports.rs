@@ -0,0 +1,76 @@
+//! Helpers for nodes that declare named ports in `graph.json`.
+//!
+//! The host passes a ported node a JSON object mapping each connected input
+//! port to its value and expects a JSON object holding every declared output
+//! port back. `i32` ports carry numbers, `string` ports strings, `json` ports
+//! any JSON value and `bytes` ports arrays of byte values.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::abi::NodeOutput;
+
+/// The input ports a node received.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inputs(Map<String, Value>);
+
+impl Inputs {
+    /// Parse the envelope the host passed in. A malformed envelope yields no
+    /// inputs rather than trapping.
+    pub fn decode(bytes: &[u8]) -> Self {
+        Self(serde_json::from_slice(bytes).unwrap_or_default())
+    }
+
+    /// Whether the port is connected.
+    pub fn contains(&self, port: &str) -> bool {
+        self.0.contains_key(port)
+    }
+
+    /// The raw JSON value of a port.
+    pub fn get(&self, port: &str) -> Option<&Value> {
+        self.0.get(port)
+    }
+
+    /// The value of an `i32` port.
+    pub fn i32(&self, port: &str) -> Option<i32> {
+        self.get(port)?.as_i64()?.try_into().ok()
+    }
+
+    /// The value of a `string` port.
+    pub fn str(&self, port: &str) -> Option<&str> {
+        self.get(port)?.as_str()
+    }
+
+    /// The value of a `bytes` port.
+    pub fn bytes(&self, port: &str) -> Option<Vec<u8>> {
+        self.get(port)?
+            .as_array()?
+            .iter()
+            .map(|byte| byte.as_u64()?.try_into().ok())
+            .collect()
+    }
+}
+
+/// The output ports a node produces.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outputs(Map<String, Value>);
+
+impl Outputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an output port, replacing any previous value.
+    pub fn set(mut self, port: &str, value: impl Into<Value>) -> Self {
+        self.0.insert(port.into(), value.into());
+        self
+    }
+}
+
+impl NodeOutput for Outputs {
+    fn into_bytes(self) -> Vec<u8> {
+        serde_json::to_vec(&Value::Object(self.0)).unwrap_or_default()
+    }
+}
@@ -0,0 +1,280 @@
+//! Host side of the node ABIs.
+//!
+//! * **v1** – the node exports `main(i32) -> i32` and exchanges a single
+//!   integer with the host.
+//! * **v2** – the node exports its `memory`, `alloc(len) -> ptr`,
+//!   `dealloc(ptr, len)` and `run(ptr, len) -> packed`. The host allocates an
+//!   input buffer with `alloc`, copies the payload in and calls `run`, which
+//!   takes ownership of the input buffer. `run` returns the output buffer as
+//!   `ptr << 32 | len`; the host copies it out and releases it with
+//!   `dealloc`.
+//!
+//! The version is selected from the module itself: a module exporting `run`
+//! speaks v2, otherwise it must export `main`. Components are a third ABI,
+//! described by a WIT world rather than raw exports; see
+//! [`crate::component`].
+//!
+//! Under either version a node may also export `error() -> packed`, which
+//! the host calls once the entry point returns. `0` means success; anything
+//! else is an error record in the node's `memory` (a little-endian `i32`
+//! code followed by a UTF-8 message) that replaces the node's output. The
+//! host releases the record with `dealloc` when the node exports it.
+//!
+//! Under either version a node may also export `init(ptr, len)`, through
+//! which it receives its `config` from the graph as JSON text before the
+//! entry point runs; nodes without a `config` are not initialised. The host
+//! allocates the buffer with `alloc` and hands it over like a v2 input, so
+//! such a node must also export `alloc` and `memory`. A node with a `config`
+//! must export `init`; errors it reports through `error` fail the node as
+//! usual.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{format_err, Instance, Memory, Module, Store};
+
+use crate::error::{Error, Result};
+
+pub(crate) const V1_ENTRY: &str = "main";
+pub(crate) const V2_ENTRY: &str = "run";
+pub(crate) const V2_ALLOC: &str = "alloc";
+pub(crate) const V2_DEALLOC: &str = "dealloc";
+pub(crate) const V2_MEMORY: &str = "memory";
+pub(crate) const ERROR_EXPORT: &str = "error";
+pub(crate) const INIT_EXPORT: &str = "init";
+
+/// Node ABI versions understood by the runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AbiVersion {
+    /// `main(i32) -> i32`.
+    V1,
+    /// Byte buffers in linear memory via `alloc`/`dealloc`/`run`.
+    V2,
+    /// A component targeting the `graph:node/node` WIT world.
+    Component,
+}
+
+impl AbiVersion {
+    /// Select the ABI a module speaks from its exports.
+    pub fn detect(module: &Module) -> Option<Self> {
+        if module.get_export(V2_ENTRY).is_some() {
+            Some(AbiVersion::V2)
+        } else if module.get_export(V1_ENTRY).is_some() {
+            Some(AbiVersion::V1)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for AbiVersion {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "v1" => Ok(AbiVersion::V1),
+            "v2" => Ok(AbiVersion::V2),
+            "component" => Ok(AbiVersion::Component),
+            _ => Err(format!(
+                "unknown ABI version {value:?}; expected v1, v2 or component"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiVersion::V1 => f.write_str("v1"),
+            AbiVersion::V2 => f.write_str("v2"),
+            AbiVersion::Component => f.write_str("component"),
+        }
+    }
+}
+
+/// Split a `run` return value into `(ptr, len)`.
+pub(crate) fn unpack(packed: u64) -> (u32, u32) {
+    ((packed >> 32) as u32, packed as u32)
+}
+
+/// Call a v1 node's `main` export.
+pub(crate) fn call_v1<T>(
+    store: &mut Store<T>,
+    instance: &Instance,
+    node: &str,
+    input: i32,
+) -> Result<i32> {
+    let entry = instance
+        .get_typed_func::<i32, i32>(&mut *store, V1_ENTRY)
+        .map_err(|err| Error::wasm(node, err))?;
+    let output = entry
+        .call(&mut *store, input)
+        .map_err(|err| Error::wasm(node, err))?;
+    check_error(store, instance, node, AbiVersion::V1)?;
+    Ok(output)
+}
+
+/// Copy `input` into a v2 node, call `run` and copy the output back out.
+pub(crate) fn call_v2<T>(
+    store: &mut Store<T>,
+    instance: &Instance,
+    node: &str,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let missing = |export| Error::MissingExport {
+        node: node.to_string(),
+        export,
+        abi: AbiVersion::V2,
+    };
+    let memory = instance
+        .get_memory(&mut *store, V2_MEMORY)
+        .ok_or_else(|| missing(V2_MEMORY))?;
+    if instance.get_func(&mut *store, V2_ALLOC).is_none() {
+        return Err(missing(V2_ALLOC));
+    }
+    if instance.get_func(&mut *store, V2_DEALLOC).is_none() {
+        return Err(missing(V2_DEALLOC));
+    }
+    let wasm = |err| Error::wasm(node, err);
+    let alloc = instance
+        .get_typed_func::<u32, u32>(&mut *store, V2_ALLOC)
+        .map_err(wasm)?;
+    let dealloc = instance
+        .get_typed_func::<(u32, u32), ()>(&mut *store, V2_DEALLOC)
+        .map_err(wasm)?;
+    let run = instance
+        .get_typed_func::<(u32, u32), u64>(&mut *store, V2_ENTRY)
+        .map_err(wasm)?;
+
+    let input_len = u32::try_from(input.len())
+        .map_err(|_| wasm(format_err!("input of {} bytes exceeds 4 GiB", input.len())))?;
+    let input_ptr = alloc.call(&mut *store, input_len).map_err(wasm)?;
+    write_guest(store, &memory, input_ptr, input).map_err(wasm)?;
+
+    let (output_ptr, output_len) = unpack(
+        run.call(&mut *store, (input_ptr, input_len))
+            .map_err(wasm)?,
+    );
+    let output = read_guest(store, &memory, output_ptr, output_len).map_err(wasm)?;
+    dealloc
+        .call(&mut *store, (output_ptr, output_len))
+        .map_err(wasm)?;
+    check_error(store, instance, node, AbiVersion::V2)?;
+    Ok(output)
+}
+
+/// Hand `config`, the node's configuration as JSON text, to its `init`
+/// export. Nothing is called for a node without a `config`, which need not
+/// export `init`.
+pub(crate) fn call_init<T>(
+    store: &mut Store<T>,
+    instance: &Instance,
+    node: &str,
+    abi: AbiVersion,
+    config: Option<&str>,
+) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    if instance.get_func(&mut *store, INIT_EXPORT).is_none() {
+        return Err(Error::ConfigIgnored(node.to_string()));
+    }
+    let missing = |export| Error::InitExport {
+        node: node.to_string(),
+        export,
+    };
+    let memory = instance
+        .get_memory(&mut *store, V2_MEMORY)
+        .ok_or_else(|| missing(V2_MEMORY))?;
+    if instance.get_func(&mut *store, V2_ALLOC).is_none() {
+        return Err(missing(V2_ALLOC));
+    }
+    let wasm = |err| Error::wasm(node, err);
+    let alloc = instance
+        .get_typed_func::<u32, u32>(&mut *store, V2_ALLOC)
+        .map_err(wasm)?;
+    let init = instance
+        .get_typed_func::<(u32, u32), ()>(&mut *store, INIT_EXPORT)
+        .map_err(wasm)?;
+
+    let len = u32::try_from(config.len()).map_err(|_| {
+        wasm(format_err!(
+            "config of {} bytes exceeds 4 GiB",
+            config.len()
+        ))
+    })?;
+    let ptr = alloc.call(&mut *store, len).map_err(wasm)?;
+    write_guest(store, &memory, ptr, config.as_bytes()).map_err(wasm)?;
+    init.call(&mut *store, (ptr, len)).map_err(wasm)?;
+    check_error(store, instance, node, abi)
+}
+
+/// Collect the error a node reported through its `error` export, if any.
+fn check_error<T>(
+    store: &mut Store<T>,
+    instance: &Instance,
+    node: &str,
+    abi: AbiVersion,
+) -> Result<()> {
+    if instance.get_func(&mut *store, ERROR_EXPORT).is_none() {
+        return Ok(());
+    }
+    let wasm = |err| Error::wasm(node, err);
+    let error = instance
+        .get_typed_func::<(), u64>(&mut *store, ERROR_EXPORT)
+        .map_err(wasm)?;
+    let packed = error.call(&mut *store, ()).map_err(wasm)?;
+    if packed == 0 {
+        return Ok(());
+    }
+    let memory =
+        instance
+            .get_memory(&mut *store, V2_MEMORY)
+            .ok_or_else(|| Error::MissingExport {
+                node: node.to_string(),
+                export: V2_MEMORY,
+                abi,
+            })?;
+    let (ptr, len) = unpack(packed);
+    let record = read_guest(store, &memory, ptr, len).map_err(wasm)?;
+    if let Ok(dealloc) = instance.get_typed_func::<(u32, u32), ()>(&mut *store, V2_DEALLOC) {
+        dealloc.call(&mut *store, (ptr, len)).map_err(wasm)?;
+    }
+    let Some((code, message)) = record.split_first_chunk::<4>() else {
+        return Err(wasm(format_err!(
+            "error record of {len} bytes is shorter than its code"
+        )));
+    };
+    Err(Error::NodeReported {
+        node: node.to_string(),
+        code: i32::from_le_bytes(*code),
+        message: String::from_utf8_lossy(message).into_owned(),
+    })
+}
+
+fn write_guest<T>(
+    store: &mut Store<T>,
+    memory: &Memory,
+    ptr: u32,
+    bytes: &[u8],
+) -> wasmtime::Result<()> {
+    memory
+        .write(store, ptr as usize, bytes)
+        .map_err(|_| format_err!("input buffer at {ptr} is outside guest memory"))
+}
+
+fn read_guest<T>(
+    store: &Store<T>,
+    memory: &Memory,
+    ptr: u32,
+    len: u32,
+) -> wasmtime::Result<Vec<u8>> {
+    let start = ptr as usize;
+    memory
+        .data(store)
+        .get(start..start + len as usize)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| format_err!("output buffer {ptr}+{len} is outside guest memory"))
+}
@@ -0,0 +1,8 @@
+//! Shorthand for `graph run`.
+
+use clap::Parser;
+use graph_runtime::cli::{self, RunArgs};
+
+fn main() -> anyhow::Result<()> {
+    cli::run(RunArgs::parse())
+}
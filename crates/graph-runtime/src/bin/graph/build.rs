@@ -0,0 +1,42 @@
+//! `graph build`.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use graph_runtime::NodeBuild;
+
+/// Build the node packages for wasm32-wasip1 and record the artifacts in
+/// the build manifest.
+#[derive(Debug, Args)]
+pub struct BuildArgs {
+    /// Nodes to build [default: every node package].
+    #[arg(value_name = "NODE")]
+    nodes: Vec<String>,
+
+    /// The Cargo workspace holding the node packages.
+    #[arg(long, value_name = "DIR", default_value = "nodes")]
+    workspace: PathBuf,
+
+    /// Fail when an artifact is larger than this many bytes, unless its
+    /// package sets `package.metadata.graph.max-size`.
+    #[arg(long, value_name = "BYTES")]
+    max_size: Option<u64>,
+}
+
+pub fn build(args: BuildArgs) -> anyhow::Result<()> {
+    let build = NodeBuild::new(&args.workspace)
+        .with_nodes(args.nodes)
+        .with_max_size(args.max_size);
+    for node in build.run()? {
+        println!(
+            "{}: {} ({} bytes, sha256 {}){}",
+            node.node,
+            node.artifact.display(),
+            node.size,
+            &node.hash[..12],
+            if node.changed { "" } else { " unchanged" }
+        );
+    }
+    println!("Wrote {}", build.manifest_path().display());
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! `graph check-node`.
+
+use std::path::PathBuf;
+
+use anyhow::bail;
+use clap::Args;
+use graph_runtime::{check_node as check, AbiVersion, Capability};
+
+/// Check wasm modules against the node ABI: entry point and export
+/// signatures, the memory export and the imports.
+#[derive(Debug, Args)]
+pub struct CheckNodeArgs {
+    /// Modules to check, as .wasm or .wat files.
+    #[arg(value_name = "WASM", required = true)]
+    wasm: Vec<PathBuf>,
+
+    /// ABI version the modules must implement: v1 or v2 [default: the one
+    /// their exports select].
+    #[arg(long, value_name = "VERSION")]
+    abi: Option<AbiVersion>,
+
+    /// Capabilities the modules may import, comma separated: log, clock,
+    /// random or fs [default: any the runtime provides].
+    #[arg(long, value_name = "CAPS", value_delimiter = ',')]
+    allow: Option<Vec<Capability>>,
+}
+
+pub fn check_node(args: CheckNodeArgs) -> anyhow::Result<()> {
+    let mut failed = 0;
+    for path in &args.wasm {
+        let conformance = check(path, args.abi, args.allow.as_deref())?;
+        let mut summary = match conformance.abi {
+            Some(abi) => format!("ABI {abi}"),
+            None => "no ABI".to_string(),
+        };
+        if !conformance.capabilities.is_empty() {
+            let capabilities: Vec<String> = conformance
+                .capabilities
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            summary.push_str(&format!(", needs {}", capabilities.join(", ")));
+        }
+        if conformance.is_ok() {
+            println!("{}: ok ({summary})", path.display());
+            continue;
+        }
+        failed += 1;
+        let count = conformance.problems.len();
+        let plural = if count == 1 { "" } else { "s" };
+        println!("{}: {count} problem{plural} ({summary})", path.display());
+        for problem in &conformance.problems {
+            println!("  {problem}");
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{failed} of {} modules do not conform to the node ABI",
+            args.wasm.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! `graph export`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Args;
+use graph_runtime::{export_graph, load_graph, read_trace, ExportFormat};
+
+/// Render a graph as Graphviz DOT or a Mermaid flowchart.
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Path to the graph specification.
+    #[arg(default_value = "graph.json")]
+    graph: PathBuf,
+
+    /// Output format: dot or mermaid.
+    #[arg(long, default_value = "dot")]
+    format: ExportFormat,
+
+    /// Annotate nodes with the inputs, outputs and durations recorded in a
+    /// trace written by `graph run --trace`, and colour the nodes that failed.
+    #[arg(long, value_name = "PATH")]
+    trace: Option<PathBuf>,
+
+    /// Write to PATH instead of stdout.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+pub fn export(args: ExportArgs) -> anyhow::Result<()> {
+    let graph = load_graph(&args.graph)?;
+    let trace = args.trace.as_ref().map(read_trace).transpose()?;
+    let rendered = export_graph(&graph, args.format, trace.as_ref());
+    match &args.output {
+        Some(path) => fs::write(path, rendered)
+            .with_context(|| format!("failed to write {}", path.display()))?,
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! The `graph` command: build, check, run and export graphs of WebAssembly
+//! nodes.
+
+use clap::{Parser, Subcommand};
+use graph_runtime::cli;
+
+mod build;
+mod check_node;
+mod export;
+
+#[derive(Debug, Parser)]
+#[command(name = "graph", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Build the node packages into wasm artifacts.
+    Build(build::BuildArgs),
+    /// Check wasm modules against the node ABI without running them.
+    CheckNode(check_node::CheckNodeArgs),
+    /// Execute a graph and print the resulting state.
+    Run(cli::RunArgs),
+    /// Render a graph as Graphviz DOT or a Mermaid flowchart.
+    Export(export::ExportArgs),
+}
+
+fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Build(args) => build::build(args),
+        Command::CheckNode(args) => check_node::check_node(args),
+        Command::Run(args) => cli::run(args),
+        Command::Export(args) => export::export(args),
+    }
+}
@@ -0,0 +1,569 @@
+//! Building node packages into wasm artifacts.
+//!
+//! Every node is a Cargo package in the `nodes` workspace, a `cdylib`
+//! depending on `graph-node-sdk`. A package names the node it provides, and
+//! optionally caps the artifact size, in its own manifest:
+//!
+//! ```toml
+//! [package.metadata.graph]
+//! node = "calcDiscount"
+//! max-size = 65536
+//! ```
+//!
+//! [`NodeBuild`] builds the packages for [`NODE_TARGET`] in release mode,
+//! leaving it to Cargo to recompile only what changed, copies each artifact
+//! next to the workspace as `<node>.wasm` and records it in
+//! [`BUILD_MANIFEST`]: the artifact path, its SHA-256 and size, the hashes of
+//! the package sources, the workspace lockfile and the path dependencies
+//! (such as this SDK) it was built from, and the compiler that built it. A package written
+//! against the component node world compiles to a core module with the
+//! canonical ABI glue; its artifact is wrapped into the component with
+//! [`componentize`](crate::componentize).
+//!
+//! The manifest doubles as a lockfile. Before running a module, the runtime
+//! looks for a manifest next to it and refuses the module if the manifest
+//! does not list it, records another hash for it, or its package sources,
+//! lockfile or path dependencies have changed since; see [`Runtime::with_stale_artifacts`]. Each run
+//! reads a manifest, and checks an artifact, only once. The recorded
+//! toolchain is informational: the artifact hash already pins the exact
+//! module, and a machine running nodes need not have a compiler at all.
+//!
+//! [`Runtime::with_stale_artifacts`]: crate::Runtime::with_stale_artifacts
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cache::content_hash;
+use crate::component;
+use crate::error::{Error, Result};
+
+/// The target nodes are compiled for.
+pub const NODE_TARGET: &str = "wasm32-wasip1";
+
+/// File name of the build manifest, in the nodes workspace.
+pub const BUILD_MANIFEST: &str = "build-manifest.json";
+
+/// What the last build produced, keyed by node id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildManifest {
+    pub nodes: IndexMap<String, BuiltArtifact>,
+}
+
+/// One node's artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuiltArtifact {
+    /// The Cargo package the node was built from.
+    pub package: String,
+    /// Path of the artifact, relative to the manifest.
+    pub artifact: String,
+    /// Hex SHA-256 of the artifact.
+    pub hash: String,
+    /// Size of the artifact in bytes.
+    pub size: u64,
+    /// Directory of the package, relative to the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Hash of the files in `source`; see [`source_hash`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+    /// Hashes of what else the artifact was built from — the workspace
+    /// lockfile and the directories of path dependencies — keyed by path
+    /// relative to the manifest. Directories hash like `source`, files by
+    /// their contents.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub dependencies: IndexMap<String, String>,
+    /// `rustc --version` of the compiler that built the artifact. Recorded
+    /// for reference; runs do not compare it with an installed compiler.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+}
+
+impl BuildManifest {
+    /// Read a manifest, treating a missing file as an empty one.
+    pub fn load(path: impl AsRef<Path>) -> Result<BuildManifest> {
+        let path = path.as_ref();
+        let json = match fs::read(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(BuildManifest::default())
+            }
+            Err(err) => return Err(Error::io(path, err)),
+        };
+        serde_json::from_slice(&json).map_err(|source| Error::Manifest {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut json = serde_json::to_string_pretty(self).expect("manifests serialize");
+        json.push('\n');
+        fs::write(path, json).map_err(|err| Error::io(path, err))
+    }
+
+    /// Why the artifact at `artifact`, hashing to `hash`, cannot be trusted,
+    /// or `None` if it matches this manifest, which lives in `dir`.
+    pub fn stale_reason(&self, dir: &Path, artifact: &Path, hash: &str) -> Result<Option<String>> {
+        self.stale_reason_with(dir, artifact, hash, path_hash)
+    }
+
+    /// [`BuildManifest::stale_reason`], hashing package sources and
+    /// dependencies with `hash_source`.
+    fn stale_reason_with(
+        &self,
+        dir: &Path,
+        artifact: &Path,
+        hash: &str,
+        mut hash_source: impl FnMut(&Path) -> io::Result<String>,
+    ) -> Result<Option<String>> {
+        let file_name = artifact.file_name().and_then(|name| name.to_str());
+        let Some(built) = self
+            .nodes
+            .values()
+            .find(|built| Some(built.artifact.as_str()) == file_name)
+        else {
+            return Ok(Some("the manifest does not list it".to_string()));
+        };
+        if built.hash != hash {
+            return Ok(Some(format!(
+                "its SHA-256 is {}, the manifest records {}",
+                &hash[..12],
+                &built.hash[..built.hash.len().min(12)]
+            )));
+        }
+        if let (Some(source), Some(expected)) = (&built.source, &built.source_hash) {
+            let source = dir.join(source);
+            // Artifacts shipped without their sources are checked by hash only.
+            if source.is_dir()
+                && hash_source(&source).map_err(|err| Error::io(&source, err))? != *expected
+            {
+                return Ok(Some(format!(
+                    "{} changed since it was built",
+                    source.display()
+                )));
+            }
+        }
+        for (path, expected) in &built.dependencies {
+            let path = dir.join(path);
+            if path.exists()
+                && hash_source(&path).map_err(|err| Error::io(&path, err))? != *expected
+            {
+                return Ok(Some(format!(
+                    "its dependency {} changed since it was built",
+                    path.display()
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Checks modules against the build manifests next to them, reading each
+/// manifest, hashing each package's sources and judging each artifact at
+/// most once. One is used per run, so edits between runs are noticed.
+#[derive(Default)]
+pub(crate) struct ManifestCheck {
+    state: Mutex<Checked>,
+}
+
+#[derive(Default)]
+struct Checked {
+    /// Manifests by directory; `None` where there is none.
+    manifests: HashMap<PathBuf, Option<BuildManifest>>,
+    sources: HashMap<PathBuf, String>,
+    /// Why each artifact, by path and hash, cannot be trusted, if it cannot.
+    verdicts: HashMap<(PathBuf, String), Option<String>>,
+}
+
+impl ManifestCheck {
+    /// Check the module at `wasm_path`, hashing to `hash`, against the build
+    /// manifest in its directory. Modules without a manifest next to them
+    /// are not checked.
+    pub(crate) fn stale_reason(&self, wasm_path: &Path, hash: &str) -> Result<Option<String>> {
+        // Held throughout, so concurrent nodes sharing a module or a
+        // manifest wait for the first check instead of repeating it.
+        let mut checked = self.state.lock().expect("manifest check poisoned");
+        let key = (wasm_path.to_path_buf(), hash.to_string());
+        if let Some(verdict) = checked.verdicts.get(&key) {
+            return Ok(verdict.clone());
+        }
+        let dir = wasm_path.parent().unwrap_or_else(|| Path::new("."));
+        if !checked.manifests.contains_key(dir) {
+            let manifest_path = dir.join(BUILD_MANIFEST);
+            let manifest = if manifest_path.is_file() {
+                Some(BuildManifest::load(&manifest_path)?)
+            } else {
+                None
+            };
+            checked.manifests.insert(dir.to_path_buf(), manifest);
+        }
+        let Checked {
+            manifests, sources, ..
+        } = &mut *checked;
+        let verdict = match &manifests[dir] {
+            Some(manifest) => manifest.stale_reason_with(dir, wasm_path, hash, |source| {
+                if let Some(hash) = sources.get(source) {
+                    return Ok(hash.clone());
+                }
+                let hash = path_hash(source)?;
+                sources.insert(source.to_path_buf(), hash.clone());
+                Ok(hash)
+            })?,
+            None => None,
+        };
+        checked.verdicts.insert(key, verdict.clone());
+        Ok(verdict)
+    }
+}
+
+/// Hash of every file under `dir`, apart from hidden files and `target`
+/// directories, together with their paths relative to `dir`.
+pub fn source_hash(dir: &Path) -> io::Result<String> {
+    let mut files = Vec::new();
+    collect_sources(dir, "", &mut files)?;
+    files.sort();
+    let mut bytes = Vec::new();
+    for relative in files {
+        let contents = fs::read(dir.join(&relative))?;
+        bytes.extend_from_slice(relative.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&contents);
+    }
+    Ok(content_hash(&bytes))
+}
+
+/// [`source_hash`] of a directory, or the content hash of a file.
+fn path_hash(path: &Path) -> io::Result<String> {
+    if path.is_dir() {
+        source_hash(path)
+    } else {
+        Ok(content_hash(&fs::read(path)?))
+    }
+}
+
+fn collect_sources(dir: &Path, prefix: &str, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || name == "target" {
+            continue;
+        }
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        if entry.file_type()?.is_dir() {
+            collect_sources(&entry.path(), &relative, files)?;
+        } else {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/// A node package found in the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodePackage {
+    /// The node id, from `package.metadata.graph.node` or the package name.
+    pub node: String,
+    pub package: String,
+    /// Name of the package's `cdylib` target, which names the artifact.
+    pub lib: String,
+    /// The directory holding the package's `Cargo.toml`.
+    pub dir: PathBuf,
+    /// `package.metadata.graph.max-size`, in bytes.
+    pub max_size: Option<u64>,
+}
+
+/// The outcome of building one node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuiltNode {
+    pub node: String,
+    pub artifact: PathBuf,
+    pub hash: String,
+    pub size: u64,
+    /// Whether the artifact differs from the one the manifest recorded.
+    pub changed: bool,
+}
+
+/// A build of the node packages in one workspace.
+#[derive(Debug, Clone)]
+pub struct NodeBuild {
+    workspace: PathBuf,
+    only: Vec<String>,
+    max_size: Option<u64>,
+}
+
+impl NodeBuild {
+    /// Build every node package in the Cargo workspace at `workspace`.
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: workspace.into(),
+            only: Vec::new(),
+            max_size: None,
+        }
+    }
+
+    /// Build only these nodes. Other entries in the manifest are kept.
+    pub fn with_nodes(mut self, nodes: Vec<String>) -> Self {
+        self.only = nodes;
+        self
+    }
+
+    /// Fail the build when an artifact is larger than `bytes`, unless its
+    /// package sets its own `max-size`.
+    pub fn with_max_size(mut self, bytes: Option<u64>) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.workspace.join(BUILD_MANIFEST)
+    }
+
+    /// Build the selected nodes, copy their artifacts into the workspace
+    /// directory and update the manifest.
+    pub fn run(&self) -> Result<Vec<BuiltNode>> {
+        let metadata = self.metadata()?;
+        let mut packages = node_packages(&metadata);
+        if !self.only.is_empty() {
+            if let Some(unknown) = self
+                .only
+                .iter()
+                .find(|node| !packages.iter().any(|package| &package.node == *node))
+            {
+                return Err(Error::UnknownNodePackage(unknown.clone()));
+            }
+            packages.retain(|package| self.only.contains(&package.node));
+        }
+
+        let mut build = self.cargo();
+        build.args(["build", "--release", "--target", NODE_TARGET]);
+        for package in &packages {
+            build.args(["-p", &package.package]);
+        }
+        run(&mut build)?;
+        let toolchain = self.toolchain()?;
+        let dependencies = dependencies(&metadata)?;
+
+        let manifest_path = self.manifest_path();
+        let mut manifest = BuildManifest::load(&manifest_path)?;
+        let output_dir = metadata.target_directory.join(NODE_TARGET).join("release");
+        let mut built = Vec::with_capacity(packages.len());
+        for package in &packages {
+            let output = output_dir.join(format!("{}.wasm", package.lib));
+            let mut bytes = fs::read(&output).map_err(|err| Error::io(&output, err))?;
+            if component::is_component_core(&bytes) {
+                bytes = component::componentize(&package.node, &bytes)?;
+            }
+            let size = bytes.len() as u64;
+            if let Some(limit) = package.max_size.or(self.max_size) {
+                if size > limit {
+                    return Err(Error::NodeTooLarge {
+                        node: package.node.clone(),
+                        size,
+                        limit,
+                    });
+                }
+            }
+            let file_name = format!("{}.wasm", package.node);
+            let artifact = self.workspace.join(&file_name);
+            let hash = content_hash(&bytes);
+            // Leave unchanged artifacts alone so their timestamps stay put.
+            let on_disk = fs::read(&artifact).ok().map(|bytes| content_hash(&bytes));
+            if on_disk.as_ref() != Some(&hash) {
+                fs::write(&artifact, &bytes).map_err(|err| Error::io(&artifact, err))?;
+            }
+            let source = package
+                .dir
+                .strip_prefix(&metadata.workspace_root)
+                .unwrap_or(&package.dir);
+            let source_hash =
+                source_hash(&package.dir).map_err(|err| Error::io(&package.dir, err))?;
+            let previous = manifest.nodes.insert(
+                package.node.clone(),
+                BuiltArtifact {
+                    package: package.package.clone(),
+                    artifact: file_name,
+                    hash: hash.clone(),
+                    size,
+                    source: Some(source.to_string_lossy().replace('\\', "/")),
+                    source_hash: Some(source_hash),
+                    dependencies: dependencies.clone(),
+                    toolchain: Some(toolchain.clone()),
+                },
+            );
+            built.push(BuiltNode {
+                node: package.node.clone(),
+                artifact,
+                changed: previous.is_none_or(|previous| previous.hash != hash),
+                hash,
+                size,
+            });
+        }
+        manifest.save(&manifest_path)?;
+        Ok(built)
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let mut command = self.cargo();
+        command.args(["metadata", "--format-version", "1"]);
+        let stdout = run(&mut command)?;
+        serde_json::from_slice(&stdout).map_err(|err| Error::Command {
+            command: "cargo metadata".to_string(),
+            reason: err.to_string(),
+        })
+    }
+
+    /// The version of the compiler Cargo builds the nodes with.
+    fn toolchain(&self) -> Result<String> {
+        let mut command = Command::new(std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()));
+        command.current_dir(&self.workspace).arg("--version");
+        let stdout = run(&mut command)?;
+        Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+    }
+
+    /// Cargo, run from the workspace so its `.cargo/config.toml` applies.
+    fn cargo(&self) -> Command {
+        let mut command = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+        command.current_dir(&self.workspace);
+        command
+    }
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    target_directory: PathBuf,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    /// `None` for packages on the local file system.
+    source: Option<String>,
+    manifest_path: PathBuf,
+    targets: Vec<Target>,
+    #[serde(default)]
+    metadata: Value,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+}
+
+/// The workspace packages with a `cdylib` target, in manifest order.
+fn node_packages(metadata: &Metadata) -> Vec<NodePackage> {
+    metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .filter_map(|package| {
+            let lib = package
+                .targets
+                .iter()
+                .find(|target| target.kind.iter().any(|kind| kind == "cdylib"))?;
+            let graph = &package.metadata["graph"];
+            Some(NodePackage {
+                node: graph["node"].as_str().unwrap_or(&package.name).to_string(),
+                package: package.name.clone(),
+                lib: lib.name.clone(),
+                dir: package
+                    .manifest_path
+                    .parent()
+                    .expect("manifests live in a directory")
+                    .to_path_buf(),
+                max_size: graph["max-size"].as_u64(),
+            })
+        })
+        .collect()
+}
+
+/// What every node of the workspace is built from besides its own package:
+/// the lockfile and the local packages outside the workspace, hashed and
+/// keyed by their path relative to the workspace root.
+fn dependencies(metadata: &Metadata) -> Result<IndexMap<String, String>> {
+    let root = &metadata.workspace_root;
+    let mut paths = vec![root.join("Cargo.lock")];
+    paths.retain(|path| path.is_file());
+    paths.extend(
+        metadata
+            .packages
+            .iter()
+            .filter(|package| {
+                package.source.is_none() && !metadata.workspace_members.contains(&package.id)
+            })
+            .filter_map(|package| package.manifest_path.parent().map(Path::to_path_buf)),
+    );
+    paths
+        .into_iter()
+        .map(|path| {
+            let hash = path_hash(&path).map_err(|err| Error::io(&path, err))?;
+            let relative = relative_path(&path, root)
+                .to_string_lossy()
+                .replace('\\', "/");
+            Ok((relative, hash))
+        })
+        .collect()
+}
+
+/// `path` relative to `base`, both absolute, stepping out of `base` with
+/// `..` where needed.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    base.components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .chain(path.components().skip(common))
+        .collect()
+}
+
+/// Run `command`, letting it report progress on stderr, and return its
+/// stdout.
+fn run(command: &mut Command) -> Result<Vec<u8>> {
+    let program = Path::new(command.get_program());
+    let mut display = program
+        .file_stem()
+        .unwrap_or(program.as_os_str())
+        .to_string_lossy()
+        .into_owned();
+    for arg in command.get_args() {
+        display.push(' ');
+        display.push_str(&arg.to_string_lossy());
+    }
+    let output = command
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map_err(|err| Error::Command {
+            command: display.clone(),
+            reason: err.to_string(),
+        })?;
+    if !output.status.success() {
+        return Err(Error::Command {
+            command: display,
+            reason: output.status.to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,263 @@
+//! Compiled module cache keyed by the SHA-256 of the module bytes.
+//!
+//! Core modules and components are cached alike; see [`Compiled`].
+//!
+//! Compiled modules are kept in memory for the lifetime of a [`Runtime`] so
+//! a graph that references the same wasm several times, or is run more than
+//! once, compiles it once. An optional on-disk cache stores Wasmtime's
+//! precompiled artifacts between processes, in a subdirectory per engine
+//! configuration so artifacts from another Wasmtime build are never loaded.
+//!
+//! [`Runtime`]: crate::Runtime
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+use wasmtime::component::Component;
+use wasmtime::{Engine, Module};
+
+use crate::component;
+use crate::error::{Error, Result};
+
+/// How module lookups were served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Modules already compiled earlier in this process.
+    pub memory_hits: usize,
+    /// Modules loaded from the on-disk cache.
+    pub disk_hits: usize,
+    /// Modules compiled from scratch.
+    pub compiled: usize,
+}
+
+impl CacheStats {
+    /// Lookups that did not need a compilation.
+    pub fn hits(&self) -> usize {
+        self.memory_hits + self.disk_hits
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} memory hit{}, {} disk hit{}, {} compiled",
+            self.memory_hits,
+            plural(self.memory_hits),
+            self.disk_hits,
+            plural(self.disk_hits),
+            self.compiled
+        )
+    }
+}
+
+/// The default location of the on-disk cache: `$XDG_CACHE_HOME/graph-runtime`
+/// or `~/.cache/graph-runtime`.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("graph-runtime"))
+}
+
+/// A compiled node: a core module speaking ABI v1 or v2, or a component.
+#[derive(Clone)]
+pub(crate) enum Compiled {
+    Module(Module),
+    Component(Component),
+}
+
+impl Compiled {
+    fn serialize(&self) -> wasmtime::Result<Vec<u8>> {
+        match self {
+            Compiled::Module(module) => module.serialize(),
+            Compiled::Component(component) => component.serialize(),
+        }
+    }
+}
+
+/// One compiled module per content hash. Each slot has its own lock so
+/// workers compiling different modules do not wait on each other, while a
+/// module referenced by several ready nodes is compiled only once.
+type Slot = Arc<Mutex<Option<Compiled>>>;
+
+#[derive(Default)]
+pub(crate) struct ModuleCache {
+    enabled: bool,
+    disk: Option<PathBuf>,
+    modules: Mutex<HashMap<String, Slot>>,
+    memory_hits: AtomicUsize,
+    disk_hits: AtomicUsize,
+    compiled: AtomicUsize,
+}
+
+impl ModuleCache {
+    pub(crate) fn new(disk: Option<PathBuf>) -> Self {
+        Self {
+            enabled: true,
+            disk,
+            ..Self::default()
+        }
+    }
+
+    /// A cache that compiles every module from scratch.
+    pub(crate) fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn disk_dir(&self) -> Option<&Path> {
+        self.disk.as_deref()
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            memory_hits: self.memory_hits.load(Ordering::Relaxed),
+            disk_hits: self.disk_hits.load(Ordering::Relaxed),
+            compiled: self.compiled.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Compile `bytes`, or reuse an earlier compilation of the same bytes.
+    /// `hash` is the [`content_hash`] of `bytes`. Components are told apart
+    /// from core modules by their header.
+    ///
+    /// The runtime keeps one engine per wasm stack limit (`None` for the
+    /// default); modules are cached per engine.
+    pub(crate) fn module(
+        &self,
+        engine: &Engine,
+        stack: Option<u64>,
+        node: &str,
+        bytes: &[u8],
+        hash: &str,
+    ) -> Result<Compiled> {
+        let is_component = component::is_component(bytes);
+        if !self.enabled {
+            return self.compile(engine, node, bytes, is_component);
+        }
+        let mut key = hash.to_string();
+        if let Some(stack) = stack {
+            key.push_str(&format!("-stack{stack}"));
+        }
+        let slot = self
+            .modules
+            .lock()
+            .expect("module cache poisoned")
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let mut slot = slot.lock().expect("module cache poisoned");
+        if let Some(module) = &*slot {
+            self.memory_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(module.clone());
+        }
+
+        let artifact = self
+            .disk
+            .as_ref()
+            .map(|dir| dir.join(engine_key(engine)).join(format!("{key}.cwasm")));
+        let module = match artifact
+            .as_deref()
+            .and_then(|path| load(engine, path, is_component))
+        {
+            Some(module) => {
+                self.disk_hits.fetch_add(1, Ordering::Relaxed);
+                module
+            }
+            None => {
+                let module = self.compile(engine, node, bytes, is_component)?;
+                // The disk cache is only an optimisation: a read-only or full
+                // cache directory must not fail the run.
+                if let Some(path) = &artifact {
+                    let _ = store(&module, path);
+                }
+                module
+            }
+        };
+        *slot = Some(module.clone());
+        Ok(module)
+    }
+
+    fn compile(
+        &self,
+        engine: &Engine,
+        node: &str,
+        bytes: &[u8],
+        is_component: bool,
+    ) -> Result<Compiled> {
+        self.compiled.fetch_add(1, Ordering::Relaxed);
+        let compiled = if is_component {
+            Component::new(engine, bytes).map(Compiled::Component)
+        } else {
+            Module::new(engine, bytes).map(Compiled::Module)
+        };
+        compiled.map_err(|err| Error::wasm(node, err))
+    }
+}
+
+/// Load a precompiled artifact, treating anything unreadable or
+/// incompatible as a miss.
+fn load(engine: &Engine, path: &Path, is_component: bool) -> Option<Compiled> {
+    // Read the artifact rather than mapping it: another process rewriting
+    // the file underneath a mapping would crash this one.
+    let artifact = fs::read(path).ok()?;
+    // SAFETY: the cache directory only holds artifacts this module wrote
+    // with `Module::serialize` or `Component::serialize`, and Wasmtime
+    // rejects artifacts produced by a different version or configuration, or
+    // of the other kind.
+    if is_component {
+        unsafe { Component::deserialize(engine, artifact) }
+            .map(Compiled::Component)
+            .ok()
+    } else {
+        unsafe { Module::deserialize(engine, artifact) }
+            .map(Compiled::Module)
+            .ok()
+    }
+}
+
+fn store(module: &Compiled, path: &Path) -> io::Result<()> {
+    write_atomic(path, &module.serialize().map_err(io::Error::other)?)
+}
+
+/// Write a cache entry through a temporary file so concurrent runs never
+/// see a partially written one. The temporary name is unique to the write,
+/// as workers of one process may store the same entry at once.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    fs::create_dir_all(path.parent().expect("cache entries live in a directory"))?;
+    let write = WRITES.fetch_add(1, Ordering::Relaxed);
+    let temp = path.with_extension(format!("{}-{write}.tmp", std::process::id()));
+    let result = fs::write(&temp, contents).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// A directory name identifying the engine's compilation settings.
+fn engine_key(engine: &Engine) -> String {
+    let mut hasher = DefaultHasher::new();
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// The hex SHA-256 of a module's bytes, which identifies it in the cache
+/// and in execution records.
+pub(crate) fn content_hash(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,190 @@
+//! The run command shared by the binaries: `graph run` and its shorthand,
+//! `graph-run`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser;
+use serde_json::{Map, Value};
+
+use crate::cache::default_cache_dir;
+use crate::executor::Runtime;
+use crate::graph::GraphSpec;
+use crate::interface::graph_outputs;
+use crate::limits::Limits;
+use crate::ports::PortType;
+use crate::report::{format_timings, log_state};
+use crate::trace::{format_chrome_trace, format_trace, read_trace};
+use crate::validate::validate_graph;
+
+/// Execute a graph of WebAssembly nodes and print the resulting state.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct RunArgs {
+    /// Path to the graph specification.
+    #[arg(default_value = "graph.json")]
+    graph: PathBuf,
+
+    /// Value for a graph input, as NAME=VALUE. VALUE is read as JSON, except
+    /// for string inputs and text that is not valid JSON; may be repeated.
+    #[arg(long = "input", value_name = "NAME=VALUE", value_parser = parse_input)]
+    inputs: Vec<(String, String)>,
+
+    /// JSON file holding an object of graph input values; --input values
+    /// take precedence.
+    #[arg(long = "inputs", value_name = "PATH")]
+    inputs_file: Option<PathBuf>,
+
+    /// Validate the graph specification without executing it.
+    #[arg(long)]
+    check: bool,
+
+    /// Maximum number of nodes to run at the same time [default: number of CPUs].
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Print when each node started and finished, and the achieved parallelism.
+    #[arg(long)]
+    timings: bool,
+
+    /// Default instruction fuel budget per node [default: unlimited].
+    #[arg(long, value_name = "UNITS")]
+    fuel: Option<u64>,
+
+    /// Default wall-clock timeout per node, in milliseconds [default: 30000].
+    #[arg(long, value_name = "MS")]
+    timeout_ms: Option<u64>,
+
+    /// Compile every module and run every node from scratch instead of
+    /// using the module and result caches.
+    #[arg(long, conflicts_with = "cache_dir")]
+    no_cache: bool,
+
+    /// Directory for precompiled modules [default: ~/.cache/graph-runtime].
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Print how many modules were compiled or served from the cache, and
+    /// how many pure nodes were served from the result cache.
+    #[arg(long)]
+    cache_stats: bool,
+
+    /// Do not print the lines nodes log to stderr.
+    #[arg(long)]
+    quiet_nodes: bool,
+
+    /// Write a JSON Lines trace of the run to PATH, even if the run fails.
+    #[arg(long, value_name = "PATH")]
+    trace: Option<PathBuf>,
+
+    /// Write the run as Chrome trace_event JSON to PATH, even if the run fails.
+    #[arg(long, value_name = "PATH")]
+    chrome_trace: Option<PathBuf>,
+
+    /// Reuse the outputs the trace from --trace recorded for every node whose
+    /// wasm and upstream outputs are unchanged, then overwrite the trace.
+    #[arg(long, requires = "trace")]
+    incremental: bool,
+
+    /// Run modules that do not match the build manifest next to them.
+    #[arg(long)]
+    allow_stale: bool,
+}
+
+/// Run the graph `cli` names and print its outputs.
+pub fn run(cli: RunArgs) -> anyhow::Result<()> {
+    let graph = validate_graph(&cli.graph)?;
+    if cli.check {
+        println!(
+            "{}: ok ({} nodes, {} edges)",
+            cli.graph.display(),
+            graph.nodes.len(),
+            graph.edges.len()
+        );
+        return Ok(());
+    }
+    let mut runtime = Runtime::new()
+        .with_inputs(graph_inputs(&graph, &cli)?)
+        .with_log_echo(!cli.quiet_nodes)
+        .with_stale_artifacts(cli.allow_stale);
+    if let Some(jobs) = cli.jobs {
+        runtime = runtime.with_jobs(jobs.into());
+    }
+    runtime = runtime.with_limits(Limits {
+        fuel: cli.fuel,
+        timeout_ms: cli.timeout_ms,
+    });
+    if cli.no_cache {
+        runtime = runtime.without_cache();
+    } else if let Some(dir) = cli.cache_dir.or_else(default_cache_dir) {
+        runtime = runtime.with_disk_cache(dir);
+    }
+    if let Some(path) = cli
+        .trace
+        .as_ref()
+        .filter(|path| cli.incremental && path.exists())
+    {
+        runtime = runtime.with_previous_run(read_trace(path)?);
+    }
+    let (state, failure) = runtime.run_graph_partial(&cli.graph);
+    if let Some(path) = &cli.trace {
+        fs::write(path, format_trace(&state, failure.as_ref()))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    if let Some(path) = &cli.chrome_trace {
+        fs::write(path, format_chrome_trace(&state, failure.as_ref()))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    if let Some(err) = failure {
+        return Err(err.into());
+    }
+    log_state(&graph, &state);
+    for (name, value) in graph_outputs(&graph, &state) {
+        println!("Output {name}: {value}");
+    }
+    if cli.timings {
+        println!("{}", format_timings(&state));
+    }
+    if cli.cache_stats {
+        println!("Module cache: {}", runtime.cache_stats());
+        println!("Result cache: {}", runtime.memo_stats());
+    }
+    Ok(())
+}
+
+fn parse_input(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, found {arg:?}")),
+    }
+}
+
+/// The graph input values given by --inputs and --input.
+fn graph_inputs(graph: &GraphSpec, cli: &RunArgs) -> anyhow::Result<Map<String, Value>> {
+    let mut inputs = match &cli.inputs_file {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            match serde_json::from_str(&text)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+            {
+                Value::Object(inputs) => inputs,
+                _ => anyhow::bail!("{} does not hold a JSON object", path.display()),
+            }
+        }
+        None => Map::new(),
+    };
+    for (name, text) in &cli.inputs {
+        let is_string = graph
+            .inputs
+            .iter()
+            .any(|input| &input.name == name && input.ty == PortType::String);
+        let value = match serde_json::from_str(text) {
+            Ok(value) if !is_string => value,
+            _ => Value::String(text.clone()),
+        };
+        inputs.insert(name.clone(), value);
+    }
+    Ok(inputs)
+}
@@ -0,0 +1,301 @@
+//! Host side of the component-model node ABI.
+//!
+//! A node may be a WebAssembly component targeting the `graph:node/node`
+//! world in `wit/node.wit` instead of a core module speaking ABI v1 or v2.
+//! The world spells out what the core ABIs encode by hand: typed port
+//! values, the node's configuration, reported errors and the `log` import.
+//! The runtime tells the two apart from the binary header, so components
+//! and core modules can be mixed freely in one graph.
+//!
+//! A component's inputs are the values of its connected input ports (`in`
+//! for a node without declared ports); it must return a value for every
+//! declared output port (`out` without ports). Values are converted to the
+//! declared port types on the way in and checked against them on the way
+//! out.
+
+use wasmparser::{Parser, Payload as Section};
+use wasmtime::component::{Component, HasSelf, Linker};
+use wasmtime::{Engine, Store};
+use wit_component::{ComponentEncoder, StringEncoding};
+use wit_parser::Resolve;
+
+use crate::error::{Error, Result};
+use crate::graph::NodeSpec;
+use crate::log::{LogLevel, LogLine};
+use crate::payload::Payload;
+use crate::ports::{PortType, PortValues, DEFAULT_INPUT, DEFAULT_OUTPUT};
+use crate::sandbox::{Capability, Imports, NodeState, Sandbox};
+
+mod bindings {
+    wasmtime::component::bindgen!({ path: "wit", world: "node" });
+}
+
+use bindings::{Level, Node, NodeImports, NodePre};
+
+/// The `graph:node` package.
+pub const NODE_WIT: &str = include_str!("../wit/node.wit");
+
+/// Name of the world every node component targets.
+pub const NODE_WORLD: &str = "node";
+
+/// The component's only import, which needs the `log` capability.
+const LOG_IMPORT: &str = "log";
+
+/// The allocator export of canonical ABI glue, which marks a core module
+/// written against the node world.
+const REALLOC_EXPORT: &str = "cabi_realloc";
+
+/// Whether `bytes`, in the binary or text format, hold a component rather
+/// than a core module. Text that does not parse is left for the core module
+/// compiler to report.
+pub(crate) fn is_component(bytes: &[u8]) -> bool {
+    // Both formats share the magic number and version; the layer field
+    // after them is 0 for core modules and 1 for components.
+    wat::parse_bytes(bytes).is_ok_and(|binary| binary.get(6..8) == Some(&[1, 0][..]))
+}
+
+/// Whether `core` is a core module implementing the node world through the
+/// canonical ABI, as the SDK's component nodes compile to, and so should be
+/// wrapped with [`componentize`].
+pub(crate) fn is_component_core(core: &[u8]) -> bool {
+    Parser::new(0).parse_all(core).any(|section| match section {
+        Ok(Section::ExportSection(exports)) => exports
+            .into_iter()
+            .any(|export| export.is_ok_and(|export| export.name == REALLOC_EXPORT)),
+        _ => false,
+    })
+}
+
+/// Wrap the core module `core` of `node`, in the binary or text format, into
+/// a component targeting the node world.
+///
+/// The module implements the world through the canonical ABI: it exports
+/// `init`, `run`, `memory` and `cabi_realloc` and may import `log` from
+/// `$root`.
+pub fn componentize(node: &str, core: &[u8]) -> Result<Vec<u8>> {
+    let encode = || -> anyhow::Result<Vec<u8>> {
+        let mut module = wat::parse_bytes(core)?.into_owned();
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("node.wit", NODE_WIT)?;
+        let world = resolve.select_world(&[package], Some(NODE_WORLD))?;
+        wit_component::embed_component_metadata(
+            &mut module,
+            &resolve,
+            world,
+            StringEncoding::UTF8,
+        )?;
+        ComponentEncoder::default()
+            .module(&module)?
+            .validate(true)
+            .encode()
+    };
+    encode().map_err(|err| Error::Componentize {
+        node: node.to_string(),
+        reason: format!("{err:#}"),
+    })
+}
+
+/// The capability each import of `component` needs: `log` for the world's
+/// `log` import, and nothing else is provided.
+pub(crate) fn import_capabilities(
+    engine: &Engine,
+    component: &Component,
+) -> Vec<(String, Option<Capability>)> {
+    component
+        .component_type()
+        .imports(engine)
+        .map(|(name, _)| {
+            let capability = (name == LOG_IMPORT).then_some(Capability::Log);
+            (name.to_string(), capability)
+        })
+        .collect()
+}
+
+/// Check the imports of `component` against `sandbox`, like
+/// [`Sandbox::check_imports`] does for core modules.
+pub(crate) fn check_imports(
+    sandbox: &Sandbox,
+    node: &str,
+    engine: &Engine,
+    component: &Component,
+) -> Result<Imports> {
+    let mut imports = Imports::default();
+    for (import, capability) in import_capabilities(engine, component) {
+        match capability {
+            Some(capability) if !sandbox.allows(capability) => {
+                return Err(Error::ImportDenied {
+                    node: node.to_string(),
+                    import,
+                    capability,
+                })
+            }
+            Some(_) => imports.log = true,
+            None => {
+                return Err(Error::UnknownImport {
+                    node: node.to_string(),
+                    import,
+                })
+            }
+        }
+    }
+    Ok(imports)
+}
+
+/// Check that `component` implements the node world's exports, returning
+/// Wasmtime's description of the first mismatch.
+pub(crate) fn check_exports(engine: &Engine, component: &Component) -> Option<String> {
+    let mut linker = Linker::<NodeState>::new(engine);
+    if let Err(err) = Node::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state) {
+        return Some(format!("{err:#}"));
+    }
+    let pre = match linker.instantiate_pre(component) {
+        Ok(pre) => pre,
+        Err(err) => return Some(format!("{err:#}")),
+    };
+    NodePre::new(pre).err().map(|err| format!("{err:#}"))
+}
+
+/// Instantiate `component`, pass it the node's configuration and run it on
+/// `inputs`, returning the inputs as converted and the outputs.
+pub(crate) fn run(
+    store: &mut Store<NodeState>,
+    component: &Component,
+    imports: Imports,
+    node: &NodeSpec,
+    inputs: PortValues,
+) -> Result<(PortValues, PortValues)> {
+    let wasm = |err| Error::wasm(&node.id, err);
+    let mut linker = Linker::new(store.engine());
+    if imports.log {
+        Node::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state).map_err(wasm)?;
+    }
+    let instance = Node::instantiate(&mut *store, component, &linker).map_err(wasm)?;
+    let reported = |error: bindings::NodeError| Error::NodeReported {
+        node: node.id.clone(),
+        code: error.code,
+        message: error.message,
+    };
+    instance
+        .call_init(&mut *store, &node.config_json())
+        .map_err(wasm)?
+        .map_err(reported)?;
+
+    let inputs = convert_inputs(node, inputs)?;
+    let ports: Vec<bindings::Port> = inputs
+        .iter()
+        .map(|(name, value)| bindings::Port {
+            name: name.clone(),
+            value: value.clone().into(),
+        })
+        .collect();
+    let returned = instance
+        .call_run(&mut *store, &ports)
+        .map_err(wasm)?
+        .map_err(reported)?;
+    let mut returned: PortValues = returned
+        .into_iter()
+        .map(|port| (port.name, port.value.into()))
+        .collect();
+    let mut outputs = PortValues::new();
+    for (name, ty) in output_ports(node) {
+        let invalid = |reason: String| Error::PortOutput {
+            node: node.id.clone(),
+            reason,
+        };
+        let value = returned
+            .shift_remove(&name)
+            .ok_or_else(|| invalid(format!("missing output port {name}")))?;
+        let value = convert(ty, value)
+            .ok_or_else(|| invalid(format!("output port {name} is not a valid {ty}")))?;
+        outputs.insert(name, value);
+    }
+    Ok((inputs, outputs))
+}
+
+/// The inputs a component receives: each connected input port converted to
+/// its declared type, or the implicit `in` port as is.
+fn convert_inputs(node: &NodeSpec, mut inputs: PortValues) -> Result<PortValues> {
+    if !node.has_ports() {
+        return Ok(inputs
+            .shift_remove(DEFAULT_INPUT)
+            .map(|value| PortValues::from([(DEFAULT_INPUT.to_string(), value)]))
+            .unwrap_or_default());
+    }
+    let mut converted = PortValues::new();
+    for port in &node.inputs {
+        let Some(value) = inputs.shift_remove(&port.name) else {
+            continue;
+        };
+        let value = convert(port.ty, value.clone()).ok_or_else(|| Error::PortValue {
+            port: crate::ports::PortRef {
+                node: node.id.clone(),
+                port: port.name.clone(),
+            },
+            expected: port.ty,
+            value,
+        })?;
+        converted.insert(port.name.clone(), value);
+    }
+    Ok(converted)
+}
+
+fn output_ports(node: &NodeSpec) -> Vec<(String, PortType)> {
+    if !node.has_ports() {
+        return vec![(DEFAULT_OUTPUT.to_string(), PortType::Any)];
+    }
+    node.outputs
+        .iter()
+        .map(|port| (port.name.clone(), port.ty))
+        .collect()
+}
+
+/// `value` as a `ty` port carries it: integers for `i32`, UTF-8 text for
+/// `string`, JSON text for `json` and bytes for `bytes`.
+fn convert(ty: PortType, value: Payload) -> Option<Payload> {
+    match ty {
+        PortType::I32 => value.to_int().map(Payload::Int),
+        PortType::String => match value {
+            Payload::Int(int) => Some(Payload::from(int.to_string().as_str())),
+            Payload::Bytes(_) => value.as_str().is_some().then_some(value),
+        },
+        PortType::Json => match value {
+            Payload::Int(_) => Some(Payload::Bytes(value.into_bytes())),
+            Payload::Bytes(ref bytes) => serde_json::from_slice::<serde_json::Value>(bytes)
+                .is_ok()
+                .then_some(value),
+        },
+        PortType::Bytes => Some(Payload::Bytes(value.into_bytes())),
+        PortType::Any => Some(value),
+    }
+}
+
+impl From<Payload> for bindings::Payload {
+    fn from(payload: Payload) -> Self {
+        match payload {
+            Payload::Int(value) => bindings::Payload::Int(value),
+            Payload::Bytes(bytes) => bindings::Payload::Bytes(bytes),
+        }
+    }
+}
+
+impl From<bindings::Payload> for Payload {
+    fn from(payload: bindings::Payload) -> Self {
+        match payload {
+            bindings::Payload::Int(value) => Payload::Int(value),
+            bindings::Payload::Bytes(bytes) => Payload::Bytes(bytes),
+        }
+    }
+}
+
+impl NodeImports for NodeState {
+    fn log(&mut self, level: Level, message: String) {
+        let level = match level {
+            Level::Trace => LogLevel::Trace,
+            Level::Debug => LogLevel::Debug,
+            Level::Info => LogLevel::Info,
+            Level::Warn => LogLevel::Warn,
+            Level::Error => LogLevel::Error,
+        };
+        self.log.push(LogLine { level, message });
+    }
+}
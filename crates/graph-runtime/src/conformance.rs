@@ -0,0 +1,323 @@
+//! Static checks of a module against the node ABI.
+//!
+//! The runtime only finds out that a module lacks its entry point, or that
+//! an export has the wrong signature, when it runs the node. [`check_node`]
+//! inspects a module's exports and imports without instantiating it, so a
+//! build or CI job can reject a broken node before any graph runs it:
+//!
+//! * the entry point and the other exports its ABI version requires are
+//!   present, with the signatures described in [`crate::abi`];
+//! * an `init` export takes the config buffer, allocated through `alloc`;
+//! * `memory` is exported whenever the host has to access it: under v2, for
+//!   the `error` and `init` exports and for `graph.log`;
+//! * every import is one the runtime provides, and, given a capability
+//!   whitelist, one the whitelist allows.
+//!
+//! A component is instead checked against the `graph:node/node` world: its
+//! exports must match the world's and it may only import `log`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use wasmtime::component::Component;
+use wasmtime::{Engine, ExternType, FuncType, Module, ValType};
+
+use crate::abi::{
+    AbiVersion, ERROR_EXPORT, INIT_EXPORT, V1_ENTRY, V2_ALLOC, V2_DEALLOC, V2_ENTRY, V2_MEMORY,
+};
+use crate::component;
+use crate::error::{Error, Result};
+use crate::log::{LOG_IMPORT, LOG_MODULE};
+use crate::sandbox::Capability;
+
+/// What [`check_node`] found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conformance {
+    /// The ABI the module was checked against: the declared one, or the one
+    /// the runtime would select from its exports.
+    pub abi: Option<AbiVersion>,
+    /// Capabilities the module's imports need, in order.
+    pub capabilities: Vec<Capability>,
+    /// Every way the module deviates from the ABI, in a sentence each.
+    pub problems: Vec<String>,
+}
+
+impl Conformance {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check the module at `path` against `abi`, or against the ABI its exports
+/// select if `None`. With `allow`, imports needing a capability outside it
+/// are problems too. Fails only if the module cannot be read or compiled.
+pub fn check_node(
+    path: impl AsRef<Path>,
+    abi: Option<AbiVersion>,
+    allow: Option<&[Capability]>,
+) -> Result<Conformance> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|err| Error::io(path, err))?;
+    let engine = Engine::default();
+    let invalid = |source| Error::InvalidModule {
+        path: path.to_path_buf(),
+        source,
+    };
+    if component::is_component(&bytes) {
+        let component = Component::new(&engine, &bytes).map_err(invalid)?;
+        return Ok(check_component(&engine, &component, abi, allow));
+    }
+    let module = Module::new(&engine, &bytes).map_err(invalid)?;
+
+    let detected = AbiVersion::detect(&module);
+    let mut conformance = Conformance {
+        abi: abi.or(detected),
+        ..Conformance::default()
+    };
+    let problems = &mut conformance.problems;
+    let mut needs_memory = Vec::new();
+    match (abi, detected) {
+        (Some(AbiVersion::Component), _) => {
+            problems.push("It is declared as a component, but it is a core module.".to_string());
+            conformance.abi = detected;
+        }
+        (None, None) => problems.push(format!(
+            "It exports neither `{V2_ENTRY}` (ABI v2) nor `{V1_ENTRY}` (ABI v1)."
+        )),
+        (Some(declared), Some(detected)) if declared != detected => problems.push(format!(
+            "It is declared as ABI {declared}, but the runtime would run it as ABI {detected}."
+        )),
+        _ => {}
+    }
+    match conformance.abi {
+        Some(AbiVersion::V1) => {
+            expect_func(
+                &module,
+                V1_ENTRY,
+                &[ValType::I32],
+                &[ValType::I32],
+                problems,
+            );
+        }
+        Some(AbiVersion::V2) => {
+            expect_func(
+                &module,
+                V2_ENTRY,
+                &[ValType::I32, ValType::I32],
+                &[ValType::I64],
+                problems,
+            );
+            expect_func(
+                &module,
+                V2_ALLOC,
+                &[ValType::I32],
+                &[ValType::I32],
+                problems,
+            );
+            expect_func(
+                &module,
+                V2_DEALLOC,
+                &[ValType::I32, ValType::I32],
+                &[],
+                problems,
+            );
+            needs_memory.push(format!("ABI {}", AbiVersion::V2));
+        }
+        Some(AbiVersion::Component) | None => {}
+    }
+    if module.get_export(ERROR_EXPORT).is_some() {
+        expect_func(&module, ERROR_EXPORT, &[], &[ValType::I64], problems);
+        needs_memory.push(format!("the `{ERROR_EXPORT}` export"));
+    }
+    if module.get_export(INIT_EXPORT).is_some() {
+        expect_func(
+            &module,
+            INIT_EXPORT,
+            &[ValType::I32, ValType::I32],
+            &[],
+            problems,
+        );
+        // ABI v2 already requires `alloc`.
+        if conformance.abi != Some(AbiVersion::V2) {
+            expect_func(
+                &module,
+                V2_ALLOC,
+                &[ValType::I32],
+                &[ValType::I32],
+                problems,
+            );
+        }
+        needs_memory.push(format!("the `{INIT_EXPORT}` export"));
+    }
+
+    for import in module.imports() {
+        let name = format!("{}.{}", import.module(), import.name());
+        let capability = match Capability::required_by(import.module(), import.name()) {
+            Ok(capability) => capability,
+            Err(()) => {
+                problems.push(format!(
+                    "It imports {name}, which the runtime does not provide."
+                ));
+                continue;
+            }
+        };
+        if (import.module(), import.name()) == (LOG_MODULE, LOG_IMPORT) {
+            match import.ty() {
+                ExternType::Func(ty)
+                    if same_types(ty.params(), &[ValType::I32, ValType::I32, ValType::I32])
+                        && ty.results().len() == 0 => {}
+                ty => problems.push(format!(
+                    "It imports {name} as {}, expected (i32, i32, i32) -> ().",
+                    describe(&ty)
+                )),
+            }
+            needs_memory.push(format!("the {name} import"));
+        }
+        let Some(capability) = capability else {
+            continue;
+        };
+        if allow.is_some_and(|allow| !allow.contains(&capability)) {
+            problems.push(format!(
+                "It imports {name}, which needs the `{capability}` capability."
+            ));
+        }
+        if !conformance.capabilities.contains(&capability) {
+            conformance.capabilities.push(capability);
+        }
+    }
+    conformance.capabilities.sort();
+
+    if !needs_memory.is_empty() {
+        let verb = if needs_memory.len() == 1 {
+            "needs"
+        } else {
+            "need"
+        };
+        let users = needs_memory.join(" and ");
+        match module.get_export(V2_MEMORY) {
+            Some(ExternType::Memory(_)) => {}
+            Some(ty) => problems.push(format!(
+                "It exports `{V2_MEMORY}` as {}, but {users} {verb} a linear memory.",
+                describe(&ty)
+            )),
+            None => problems.push(format!(
+                "It does not export `{V2_MEMORY}`, which {users} {verb}."
+            )),
+        }
+    }
+    Ok(conformance)
+}
+
+/// Check a component against the node world.
+fn check_component(
+    engine: &Engine,
+    component: &Component,
+    abi: Option<AbiVersion>,
+    allow: Option<&[Capability]>,
+) -> Conformance {
+    let mut conformance = Conformance {
+        abi: Some(AbiVersion::Component),
+        ..Conformance::default()
+    };
+    let problems = &mut conformance.problems;
+    if let Some(declared) = abi.filter(|&abi| abi != AbiVersion::Component) {
+        problems.push(format!(
+            "It is declared as ABI {declared}, but it is a component."
+        ));
+    }
+    let mut unknown_imports = false;
+    for (import, capability) in component::import_capabilities(engine, component) {
+        let Some(capability) = capability else {
+            unknown_imports = true;
+            problems.push(format!(
+                "It imports {import}, which the runtime does not provide."
+            ));
+            continue;
+        };
+        if allow.is_some_and(|allow| !allow.contains(&capability)) {
+            problems.push(format!(
+                "It imports {import}, which needs the `{capability}` capability."
+            ));
+        }
+        conformance.capabilities.push(capability);
+    }
+    // Type checking links the imports too, so it can only describe the
+    // exports once every import is known.
+    if let Some(mismatch) = (!unknown_imports)
+        .then(|| component::check_exports(engine, component))
+        .flatten()
+    {
+        problems.push(format!(
+            "It does not implement the `graph:node/{}` world: {mismatch}.",
+            component::NODE_WORLD
+        ));
+    }
+    conformance
+}
+
+/// Record a problem unless `module` exports a function `name` taking
+/// `params` and returning `results`.
+fn expect_func(
+    module: &Module,
+    name: &str,
+    params: &[ValType],
+    results: &[ValType],
+    problems: &mut Vec<String>,
+) {
+    let expected = signature(params.iter().cloned(), results.iter().cloned());
+    match module.get_export(name) {
+        Some(ExternType::Func(ty))
+            if same_types(ty.params(), params) && same_types(ty.results(), results) => {}
+        Some(ty) => problems.push(format!(
+            "`{name}` is {}, expected {expected}.",
+            describe(&ty)
+        )),
+        None => problems.push(format!("It does not export `{name}`, expected {expected}.")),
+    }
+}
+
+fn same_types(actual: impl ExactSizeIterator<Item = ValType>, expected: &[ValType]) -> bool {
+    actual.len() == expected.len()
+        && actual
+            .zip(expected)
+            .all(|(actual, expected)| ValType::eq(&actual, expected))
+}
+
+fn describe(ty: &ExternType) -> String {
+    match ty {
+        ExternType::Func(ty) => format!("a function {}", func_signature(ty)),
+        ExternType::Memory(_) => "a memory".to_string(),
+        ExternType::Table(_) => "a table".to_string(),
+        ExternType::Global(_) => "a global".to_string(),
+        ExternType::Tag(_) => "a tag".to_string(),
+    }
+}
+
+fn func_signature(ty: &FuncType) -> String {
+    signature(ty.params(), ty.results())
+}
+
+/// `(i32, i32) -> i64`, in the notation the ABI documentation uses.
+fn signature(
+    params: impl Iterator<Item = ValType>,
+    results: impl Iterator<Item = ValType>,
+) -> String {
+    let list = |types: Vec<ValType>| {
+        let mut out = String::new();
+        for (index, ty) in types.iter().enumerate() {
+            if index > 0 {
+                out.push_str(", ");
+            }
+            let _ = write!(out, "{ty}");
+        }
+        out
+    };
+    let results: Vec<ValType> = results.collect();
+    let results = match results.len() {
+        0 => "()".to_string(),
+        1 => list(results),
+        _ => format!("({})", list(results)),
+    };
+    format!("({}) -> {results}", list(params.collect()))
+}
@@ -0,0 +1,294 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::abi::AbiVersion;
+use crate::failure::NodeError;
+use crate::payload::Payload;
+use crate::ports::{PortDirection, PortRef, PortType};
+use crate::sandbox::{Capability, Exceeded};
+use crate::topo::Cycle;
+use crate::validate::ValidationReport;
+
+/// Convenience alias used throughout the runtime.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors produced while loading, ordering or executing a graph.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("failed to parse graph specification {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("{0}")]
+    Invalid(ValidationReport),
+
+    #[error("failed to parse trace {} line {line}: {source}", path.display())]
+    Trace {
+        path: PathBuf,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Graph specification must contain a nodes array.")]
+    MissingNodes,
+
+    #[error("Unknown key `{key}`.{}", did_you_mean(.suggestion))]
+    UnknownKey {
+        key: String,
+        suggestion: Option<&'static str>,
+    },
+
+    #[error("Missing required key `{0}`.")]
+    MissingKey(&'static str),
+
+    #[error("Expected {expected}, found {found}.")]
+    InvalidValue {
+        expected: &'static str,
+        found: String,
+    },
+
+    #[error("Node {node} refers to missing wasm file {}.", path.display())]
+    MissingWasm { node: String, path: PathBuf },
+
+    #[error("Subgraph {} embeds itself.", .0.display())]
+    RecursiveSubgraph(PathBuf),
+
+    #[error("Subgraph {} declares no outputs, so nothing can use its results.", .0.display())]
+    SubgraphOutputs(PathBuf),
+
+    #[error("A subgraph node cannot set `{0}`; set it on the nodes of the embedded graph.")]
+    SubgraphNodeKey(&'static str),
+
+    #[error("Duplicate node id detected: {0}")]
+    DuplicateNode(String),
+
+    #[error("Node {node} depends on unknown node {dependency}")]
+    UnknownDependency { node: String, dependency: String },
+
+    #[error("Edge endpoint {port} references an unknown node.")]
+    UnknownEdgeNode { port: PortRef },
+
+    #[error("Edge endpoint {port} is not an {direction} port of node {}.", port.node)]
+    UnknownPort {
+        port: PortRef,
+        direction: PortDirection,
+    },
+
+    #[error("Edge {from} -> {to} connects a {from_type} output to a {to_type} input.")]
+    PortTypeMismatch {
+        from: PortRef,
+        from_type: PortType,
+        to: PortRef,
+        to_type: PortType,
+    },
+
+    #[error(
+        "Graph {direction} {name} refers to {port}, which is not an {direction} port in the graph."
+    )]
+    UnknownGraphPort {
+        name: String,
+        port: PortRef,
+        direction: PortDirection,
+    },
+
+    #[error("Graph input {input} is {ty}, but feeds the {to_type} input {to}.")]
+    GraphInputTypeMismatch {
+        input: String,
+        ty: PortType,
+        to: PortRef,
+        to_type: PortType,
+    },
+
+    #[error("Duplicate graph {direction} detected: {name}")]
+    DuplicateGraphPort {
+        name: String,
+        direction: PortDirection,
+    },
+
+    #[error("Invalid report template: {0}.")]
+    InvalidTemplate(String),
+
+    #[error("Report template refers to {0}, which is not an output port in the graph.")]
+    UnknownReportPort(PortRef),
+
+    #[error("Input port {0} has more than one incoming edge.")]
+    DuplicateEdge(PortRef),
+
+    #[error("Required input port {0} is not connected.")]
+    UnconnectedPort(PortRef),
+
+    #[error("{}; topological sort failed.", cycle_list(.cycles))]
+    Cycle { cycles: Vec<Cycle> },
+
+    #[error("Node {node} exhausted its fuel budget of {fuel} units.")]
+    FuelExhausted { node: String, fuel: u64 },
+
+    #[error("Node {node} timed out after {}ms.", timeout.as_millis())]
+    NodeTimedOut { node: String, timeout: Duration },
+
+    #[error("{0}")]
+    Trap(Box<NodeError>),
+
+    #[error("Node {node} reported error {code}: {message}")]
+    NodeReported {
+        node: String,
+        code: i32,
+        message: String,
+    },
+
+    #[error("Node {node} has an invalid onError default: {reason}")]
+    InvalidDefault { node: String, reason: String },
+
+    #[error("Node {node} imports {import}, but its sandbox policy does not allow `{capability}`.")]
+    ImportDenied {
+        node: String,
+        import: String,
+        capability: Capability,
+    },
+
+    #[error("Node {node} imports {import}, which the runtime does not provide.")]
+    UnknownImport { node: String, import: String },
+
+    #[error(
+        "Node {node} exceeded its sandbox limit of {} {} (requested {}).",
+        exceeded.limit, exceeded.resource, exceeded.requested
+    )]
+    SandboxLimit { node: String, exceeded: Exceeded },
+
+    #[error("failed to preopen {}: {reason}", path.display())]
+    Preopen { path: PathBuf, reason: String },
+
+    #[error("{} is not a valid WebAssembly module: {source:#}", path.display())]
+    InvalidModule {
+        path: PathBuf,
+        source: wasmtime::Error,
+    },
+
+    #[error("failed to parse build manifest {}: {source}", path.display())]
+    Manifest {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("`{command}` failed: {reason}")]
+    Command { command: String, reason: String },
+
+    #[error("failed to turn node {node} into a component: {reason}")]
+    Componentize { node: String, reason: String },
+
+    #[error("No package in the nodes workspace provides node {0}.")]
+    UnknownNodePackage(String),
+
+    #[error("Node {node} is {size} bytes, over its size limit of {limit} bytes.")]
+    NodeTooLarge { node: String, size: u64, limit: u64 },
+
+    #[error("Node {node} runs {}, which does not match the build manifest: {reason}.", path.display())]
+    StaleArtifact {
+        node: String,
+        path: PathBuf,
+        reason: String,
+    },
+
+    #[error("Graph has no input named {0}.")]
+    UnknownGraphInput(String),
+
+    #[error("Graph input {0} is required, but no value was supplied.")]
+    MissingGraphInput(String),
+
+    #[error("Graph input {input} expects {expected} but received {value}.")]
+    GraphInputValue {
+        input: String,
+        expected: PortType,
+        value: serde_json::Value,
+    },
+
+    #[error("Missing state for dependency {0}")]
+    MissingState(String),
+
+    #[error("Node {0} does not export a main function.")]
+    MissingEntry(String),
+
+    #[error("Node {0} has a config, but its module does not export `init`.")]
+    ConfigIgnored(String),
+
+    #[error("Node {node} exports `init` but not {export}, which passing its config requires.")]
+    InitExport { node: String, export: &'static str },
+
+    #[error("Node {node} does not export {export}, which ABI {abi} requires.")]
+    MissingExport {
+        node: String,
+        export: &'static str,
+        abi: AbiVersion,
+    },
+
+    #[error("Node {node} expects an integer input but received {input}.")]
+    IncompatibleInput { node: String, input: Payload },
+
+    #[error("Port {port} expects {expected} but received {value}.")]
+    PortValue {
+        port: PortRef,
+        expected: PortType,
+        value: Payload,
+    },
+
+    #[error("Node {node} cannot map its ports onto ABI {abi}: {reason}")]
+    PortAbi {
+        node: String,
+        abi: AbiVersion,
+        reason: String,
+    },
+
+    #[error("Node {node} returned a malformed port envelope: {reason}")]
+    PortEnvelope { node: String, reason: String },
+
+    #[error("Node {node} returned invalid outputs: {reason}")]
+    PortOutput { node: String, reason: String },
+
+    #[error("node {node} failed: {source:#}")]
+    Wasm {
+        node: String,
+        source: wasmtime::Error,
+    },
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn wasm(node: impl Into<String>, source: wasmtime::Error) -> Self {
+        Error::Wasm {
+            node: node.into(),
+            source,
+        }
+    }
+}
+
+fn did_you_mean(suggestion: &Option<&'static str>) -> String {
+    suggestion.map_or_else(String::new, |key| format!(" Did you mean `{key}`?"))
+}
+
+/// Describe one or more cycles, e.g. `Graph contains 2 cycles: a -> a; b -> c -> b`.
+fn cycle_list(cycles: &[Cycle]) -> String {
+    let rendered: Vec<String> = cycles.iter().map(Cycle::to_string).collect();
+    match rendered.len() {
+        1 => format!("Graph contains a cycle: {}", rendered[0]),
+        count => format!("Graph contains {count} cycles: {}", rendered.join("; ")),
+    }
+}
@@ -0,0 +1,822 @@
+use std::collections::HashMap;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use wasmtime::{Config, Engine, Linker, Module, Store};
+use wasmtime_wasi::p1;
+
+use crate::abi::{self, AbiVersion};
+use crate::build::ManifestCheck;
+use crate::cache::{content_hash, CacheStats, Compiled, ModuleCache};
+use crate::component;
+use crate::error::{Error, Result};
+use crate::failure::{self, NodeError, OnError, Outcome};
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::interface::graph_outputs;
+use crate::limits::{self, Limits, DEFAULT_TIMEOUT};
+use crate::log::{self, LogLine, NodeLog};
+use crate::memo::{Memo, MemoStats, ResultCache};
+use crate::payload::Payload;
+use crate::ports::{PortRef, PortType, PortValues, DEFAULT_INPUT, DEFAULT_OUTPUT};
+use crate::sandbox::{Imports, NodeState, Sandbox};
+use crate::scheduler::{self, Timing};
+use crate::topo::topo_sort;
+use crate::trace::{NodeEvent, RunTrace};
+use crate::validate::validate_graph;
+
+/// The observed inputs and outputs of a single node execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    /// ABI the node was invoked through; `None` when it was skipped because
+    /// of an upstream node, without loading its module.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abi: Option<AbiVersion>,
+    /// Hex SHA-256 of the node's wasm module; empty when it was skipped
+    /// because of an upstream node.
+    #[serde(default)]
+    pub wasm_hash: String,
+    /// The node's `config`, as handed to it before it ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Map<String, Value>>,
+    /// Input port values exactly as handed to the node, after any conversion.
+    pub inputs: PortValues,
+    pub outputs: PortValues,
+    pub dependencies: Vec<String>,
+    /// Hex SHA-256 of the node's port declarations and of what fed each of
+    /// its input ports.
+    #[serde(default)]
+    pub wiring_hash: String,
+    /// When and where the node ran.
+    pub timing: Timing,
+    /// Whether the node succeeded, or failed and was handled by its
+    /// `onError` policy.
+    #[serde(default)]
+    pub outcome: Outcome,
+    /// How many times the node ran; `0` when skipped because of an upstream
+    /// node.
+    pub attempts: u32,
+    /// The last failure, when the node failed at least once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<NodeError>,
+    /// Lines the node logged through `graph.log`, across every attempt.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<LogLine>,
+    /// Whether the outputs were served from the result cache instead of
+    /// running the node; see [`NodeSpec::pure`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// Whether the outputs were reused from the previous run instead of
+    /// running the node; see [`Runtime::with_previous_run`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reused: bool,
+}
+
+impl ExecutionRecord {
+    /// The value of the node's first input port, if it received one.
+    pub fn input(&self) -> Option<&Payload> {
+        self.inputs.first().map(|(_, value)| value)
+    }
+
+    /// The value of the node's first output port.
+    pub fn output(&self) -> Option<&Payload> {
+        self.outputs.first().map(|(_, value)| value)
+    }
+}
+
+/// Execution records keyed by node id, in topological order regardless of
+/// the order in which parallel nodes happened to finish.
+pub type ExecutionState = IndexMap<String, ExecutionRecord>;
+
+/// Resolve the values that should be sent into a node's input ports.
+///
+/// Connected ports take the upstream output their edge points at. A node
+/// without declared ports whose implicit input is not connected reduces the
+/// upstream outputs to a single value by taking the last dependency's output,
+/// which keeps the original streaming-pipeline contract; root nodes receive
+/// `0`. Ports fed by a graph input take its value from `graph_inputs`, as
+/// bound by [`GraphSpec::bind_inputs`]. Unconnected optional ports are left
+/// out.
+pub fn resolve_inputs(
+    graph: &GraphSpec,
+    node: &NodeSpec,
+    state: &ExecutionState,
+    graph_inputs: &PortValues,
+) -> Result<PortValues> {
+    let mut values = PortValues::new();
+    for port in node.input_ports() {
+        let target = PortRef {
+            node: node.id.clone(),
+            port: port.name.clone(),
+        };
+        let edge = graph
+            .incoming_edges(&node.id)
+            .find(|edge| edge.to.port == port.name);
+        let value = match (edge, graph.input_feeding(&target)) {
+            (Some(edge), _) => upstream_output(state, &edge.from)?,
+            (None, Some(input)) => graph_inputs
+                .get(&input.name)
+                .cloned()
+                .ok_or_else(|| Error::MissingGraphInput(input.name.clone()))?,
+            (None, None) if !node.has_ports() => last_dependency_output(node, state)?,
+            (None, None) if port.required => return Err(Error::UnconnectedPort(target)),
+            (None, None) => continue,
+        };
+        values.insert(port.name, value);
+    }
+    Ok(values)
+}
+
+/// Whether any node `node` depends on was skipped, which skips `node` too.
+pub(crate) fn upstream_skipped(graph: &GraphSpec, node: &NodeSpec, state: &ExecutionState) -> bool {
+    graph.dependencies(node).iter().any(|dep| {
+        state
+            .get(dep)
+            .is_some_and(|record| record.outcome == Outcome::Skipped)
+    })
+}
+
+fn upstream_output(state: &ExecutionState, from: &PortRef) -> Result<Payload> {
+    state
+        .get(&from.node)
+        .and_then(|record| record.outputs.get(&from.port))
+        .cloned()
+        .ok_or_else(|| Error::MissingState(from.to_string()))
+}
+
+fn last_dependency_output(node: &NodeSpec, state: &ExecutionState) -> Result<Payload> {
+    let Some(last_dependency) = node.depends_on.last() else {
+        return Ok(Payload::Int(0));
+    };
+    state
+        .get(last_dependency)
+        .and_then(ExecutionRecord::output)
+        .cloned()
+        .ok_or_else(|| Error::MissingState(last_dependency.clone()))
+}
+
+/// Map a v1 node's ports onto `main(i32) -> i32`: at most one input and
+/// exactly one output, both integers.
+fn v1_ports(node: &NodeSpec) -> Result<(Option<String>, String)> {
+    if !node.has_ports() {
+        return Ok((Some(DEFAULT_INPUT.to_string()), DEFAULT_OUTPUT.to_string()));
+    }
+    let reject = |reason: &str| Error::PortAbi {
+        node: node.id.clone(),
+        abi: AbiVersion::V1,
+        reason: reason.to_string(),
+    };
+    let integer = |ty: PortType| matches!(ty, PortType::I32 | PortType::Any);
+    if node.inputs.len() > 1 {
+        return Err(reject("it accepts at most one input port"));
+    }
+    let [output] = node.outputs.as_slice() else {
+        return Err(reject("it produces exactly one output port"));
+    };
+    if !node
+        .inputs
+        .iter()
+        .chain([output])
+        .all(|port| integer(port.ty))
+    {
+        return Err(reject("its ports must be i32"));
+    }
+    Ok((
+        node.inputs.first().map(|port| port.name.clone()),
+        output.name.clone(),
+    ))
+}
+
+/// Encode port values as the JSON object a ported v2 node receives.
+fn encode_envelope(node: &NodeSpec, inputs: &PortValues) -> Result<Vec<u8>> {
+    let mut envelope = serde_json::Map::new();
+    for port in &node.inputs {
+        let Some(value) = inputs.get(&port.name) else {
+            continue;
+        };
+        let encoded = port.ty.encode(value).ok_or_else(|| Error::PortValue {
+            port: PortRef {
+                node: node.id.clone(),
+                port: port.name.clone(),
+            },
+            expected: port.ty,
+            value: value.clone(),
+        })?;
+        envelope.insert(port.name.clone(), encoded);
+    }
+    Ok(serde_json::to_vec(&envelope).expect("JSON maps always serialize"))
+}
+
+/// Decode the JSON object a ported v2 node returns into its output ports.
+fn decode_envelope(node: &NodeSpec, bytes: &[u8]) -> Result<PortValues> {
+    let malformed = |reason: String| Error::PortEnvelope {
+        node: node.id.clone(),
+        reason,
+    };
+    let mut envelope: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(bytes).map_err(|err| malformed(err.to_string()))?;
+    let mut outputs = PortValues::new();
+    for port in &node.outputs {
+        let value = envelope
+            .remove(&port.name)
+            .ok_or_else(|| malformed(format!("missing output port {}", port.name)))?;
+        let decoded = port.ty.decode(value).ok_or_else(|| {
+            malformed(format!(
+                "output port {} is not a valid {}",
+                port.name, port.ty
+            ))
+        })?;
+        outputs.insert(port.name.clone(), decoded);
+    }
+    Ok(outputs)
+}
+
+/// Owns the WebAssembly engines shared by every node in a run.
+///
+/// Compiled modules are cached in memory for the lifetime of the runtime
+/// (and its clones); see [`Runtime::with_disk_cache`] to keep them between
+/// processes too.
+#[derive(Clone)]
+pub struct Runtime {
+    engines: Arc<Engines>,
+    jobs: NonZeroUsize,
+    cache: Arc<ModuleCache>,
+    memo: Arc<ResultCache>,
+    previous: Option<Arc<RunTrace>>,
+    inputs: Map<String, Value>,
+    limits: Limits,
+    echo_logs: bool,
+    allow_stale: bool,
+    manifests: Arc<ManifestCheck>,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self {
+            engines: Arc::default(),
+            jobs: thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            cache: Arc::new(ModuleCache::new(None)),
+            memo: Arc::new(ResultCache::new(None)),
+            previous: None,
+            inputs: Map::new(),
+            limits: Limits {
+                fuel: None,
+                timeout_ms: Some(DEFAULT_TIMEOUT.as_millis() as u64),
+            },
+            echo_logs: false,
+            allow_stale: false,
+            manifests: Arc::default(),
+        }
+    }
+}
+
+/// A node's compiled module, ready to be instantiated, and the budgets and
+/// sandbox it runs under.
+struct Prepared {
+    engine: Engine,
+    module: Compiled,
+    wasm_hash: String,
+    abi: AbiVersion,
+    imports: Imports,
+    limits: Limits,
+    sandbox: Sandbox,
+}
+
+/// The result of running one node under its `onError` policy.
+pub(crate) struct Call {
+    pub(crate) abi: Option<AbiVersion>,
+    pub(crate) wasm_hash: String,
+    pub(crate) config: Option<Map<String, Value>>,
+    pub(crate) inputs: PortValues,
+    pub(crate) outputs: PortValues,
+    pub(crate) outcome: Outcome,
+    pub(crate) attempts: u32,
+    pub(crate) error: Option<NodeError>,
+    pub(crate) logs: Vec<LogLine>,
+    pub(crate) cached: bool,
+    pub(crate) reused: bool,
+}
+
+impl Call {
+    pub(crate) fn record(
+        self,
+        graph: &GraphSpec,
+        node: &NodeSpec,
+        timing: Timing,
+    ) -> ExecutionRecord {
+        ExecutionRecord {
+            abi: self.abi,
+            wasm_hash: self.wasm_hash,
+            config: self.config,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            dependencies: graph.dependencies(node),
+            wiring_hash: graph.wiring_hash(node),
+            timing,
+            outcome: self.outcome,
+            attempts: self.attempts,
+            error: self.error,
+            logs: self.logs,
+            cached: self.cached,
+            reused: self.reused,
+        }
+    }
+}
+
+/// Wasmtime fixes the wasm stack limit per engine, so the runtime keeps one
+/// engine per `maxStackBytes` in use, created on first use.
+#[derive(Default)]
+struct Engines {
+    by_stack: Mutex<HashMap<Option<u64>, Engine>>,
+}
+
+impl Engines {
+    fn get(&self, max_stack_bytes: Option<u64>) -> Engine {
+        self.by_stack
+            .lock()
+            .expect("engine pool poisoned")
+            .entry(max_stack_bytes)
+            .or_insert_with(|| {
+                let mut config = Config::new();
+                config.consume_fuel(true).epoch_interruption(true);
+                if let Some(bytes) = max_stack_bytes {
+                    config.max_wasm_stack(bytes as usize);
+                }
+                let engine = Engine::new(&config).expect("the engine configuration is valid");
+                limits::start_ticker(&engine);
+                engine
+            })
+            .clone()
+    }
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run at most `jobs` nodes at the same time. Defaults to the number of
+    /// available CPUs; `0` is treated as `1`.
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = NonZeroUsize::new(jobs).unwrap_or(NonZeroUsize::MIN);
+        self
+    }
+
+    /// The maximum number of nodes run concurrently.
+    pub fn jobs(&self) -> usize {
+        self.jobs.get()
+    }
+
+    /// Budgets for nodes that neither they nor their graph configure. Unset
+    /// fields keep the built-in defaults: unlimited fuel and a
+    /// [`DEFAULT_TIMEOUT`] timeout.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits.or(self.limits);
+        self
+    }
+
+    /// Print each line a node logs to stderr as it arrives, prefixed with
+    /// the node id. Lines are recorded in the execution state either way.
+    pub fn with_log_echo(mut self, echo: bool) -> Self {
+        self.echo_logs = echo;
+        self
+    }
+
+    /// Run modules that do not match the build manifest next to them, e.g.
+    /// while iterating on a node without rebuilding every time. By default
+    /// such modules fail with [`Error::StaleArtifact`].
+    pub fn with_stale_artifacts(mut self, allow: bool) -> Self {
+        self.allow_stale = allow;
+        self
+    }
+
+    /// Run incrementally against `trace`, the trace of a previous run: a
+    /// node whose module, `config`, ports and wiring are unchanged and whose
+    /// dependencies and graph inputs produce the same values as in that run
+    /// is not executed again; its recorded outputs are reused instead. Nodes
+    /// the trace does not show succeeding always run.
+    pub fn with_previous_run(mut self, trace: RunTrace) -> Self {
+        self.previous = Some(Arc::new(trace));
+        self
+    }
+
+    /// The previous execution of `node` that may stand in for running it,
+    /// subject to its module being unchanged.
+    pub(crate) fn previous_run(
+        &self,
+        graph: &GraphSpec,
+        node: &NodeSpec,
+        state: &ExecutionState,
+        graph_inputs: &PortValues,
+    ) -> Option<&NodeEvent> {
+        self.previous
+            .as_ref()?
+            .reusable(graph, node, state, graph_inputs)
+    }
+
+    /// Values for the graph's `inputs`, keyed by input name. Inputs left out
+    /// take their `default`; running a graph fails with
+    /// [`Error::MissingGraphInput`] if one without a default is missing.
+    pub fn with_inputs(mut self, inputs: Map<String, Value>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    /// Also store precompiled modules and the results of pure nodes under
+    /// `dir` and reuse them in later runs. Starts from empty in-memory
+    /// caches.
+    pub fn with_disk_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.memo = Arc::new(ResultCache::new(Some(dir.clone())));
+        self.cache = Arc::new(ModuleCache::new(Some(dir)));
+        self
+    }
+
+    /// Compile every module from scratch and run every node, pure or not,
+    /// in memory and on disk alike.
+    pub fn without_cache(mut self) -> Self {
+        self.cache = Arc::new(ModuleCache::disabled());
+        self.memo = Arc::new(ResultCache::disabled());
+        self
+    }
+
+    /// The on-disk cache directory, if one is configured.
+    pub fn cache_dir(&self) -> Option<&Path> {
+        self.cache.disk_dir()
+    }
+
+    /// How module lookups have been served so far.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// How pure nodes have been served so far.
+    pub fn memo_stats(&self) -> MemoStats {
+        self.memo.stats()
+    }
+
+    /// Execute a single WASM node and persist its results into the state map.
+    /// The recorded timing is relative to this call.
+    pub fn execute_node(
+        &self,
+        base_dir: &Path,
+        graph: &GraphSpec,
+        node: &NodeSpec,
+        state: &mut ExecutionState,
+    ) -> Result<()> {
+        let epoch = Instant::now();
+        let graph_inputs = graph.bind_inputs(&self.inputs)?;
+        let inputs = if upstream_skipped(graph, node, state) {
+            None
+        } else {
+            Some(resolve_inputs(graph, node, state, &graph_inputs)?)
+        };
+        let previous = self.previous_run(graph, node, state, &graph_inputs);
+        let call = self
+            .for_run()
+            .call_node(base_dir, graph, node, inputs, previous)?;
+        let timing = Timing {
+            worker: 0,
+            started: Duration::ZERO,
+            finished: epoch.elapsed(),
+        };
+        state.insert(node.id.clone(), call.record(graph, node, timing));
+        Ok(())
+    }
+
+    /// Run a node with already resolved inputs within its budgets and
+    /// sandbox, applying its `onError` policy to failures. `None` inputs mean
+    /// an upstream node was skipped, so this one is skipped too. `previous`
+    /// is the node's reusable execution from the previous run, if any.
+    pub(crate) fn call_node(
+        &self,
+        base_dir: &Path,
+        graph: &GraphSpec,
+        node: &NodeSpec,
+        inputs: Option<PortValues>,
+        previous: Option<&NodeEvent>,
+    ) -> Result<Call> {
+        let mut call = Call {
+            abi: None,
+            wasm_hash: String::new(),
+            config: node.config.clone(),
+            inputs: PortValues::new(),
+            outputs: PortValues::new(),
+            outcome: Outcome::Skipped,
+            attempts: 0,
+            error: None,
+            logs: Vec::new(),
+            cached: false,
+            reused: false,
+        };
+        // A skipped node's module is not even loaded, so a missing or broken
+        // one does not fail the run.
+        let Some(inputs) = inputs else {
+            return Ok(call);
+        };
+        let limits = Limits::for_node(graph, node, self.limits);
+        let sandbox = Sandbox::for_node(graph, node);
+        let prepared = self.prepare(base_dir, node, limits, sandbox)?;
+        call.abi = Some(prepared.abi);
+        call.wasm_hash = prepared.wasm_hash.clone();
+        let reusable = previous
+            .filter(|event| event.wasm_hash == prepared.wasm_hash && event.config == node.config);
+        if let Some((inputs, outputs)) = reusable.and_then(NodeEvent::values) {
+            call.inputs = inputs;
+            call.outputs = outputs;
+            call.outcome = Outcome::Succeeded;
+            call.reused = true;
+            return Ok(call);
+        }
+        let memo_key = self.memo.key(node, &prepared.wasm_hash, &inputs);
+        if let Some(memo) = memo_key.as_deref().and_then(|key| self.memo.get(key)) {
+            call.inputs = memo.inputs;
+            call.outputs = memo.outputs;
+            call.outcome = Outcome::Succeeded;
+            call.cached = true;
+            return Ok(call);
+        }
+
+        loop {
+            call.attempts += 1;
+            let result = self.invoke(base_dir, node, inputs.clone(), &prepared, &mut call.logs);
+            let err = match result {
+                Ok((inputs, outputs)) => {
+                    if let Some(key) = memo_key {
+                        let memo = Memo {
+                            inputs: inputs.clone(),
+                            outputs: outputs.clone(),
+                        };
+                        self.memo.put(key, memo);
+                    }
+                    call.inputs = inputs;
+                    call.outputs = outputs;
+                    call.outcome = Outcome::Succeeded;
+                    return Ok(call);
+                }
+                Err(err) => failure::classify(&node.id, limits.classify(&node.id, err), &inputs),
+            };
+            let Some(failure) = NodeError::from_error(&err, &inputs) else {
+                return Err(err);
+            };
+            call.error = Some(failure);
+            if call.attempts < node.on_error.attempts() {
+                continue;
+            }
+            call.inputs = inputs;
+            return match node.on_error.default_outputs(node) {
+                Some(outputs) => {
+                    call.outputs = outputs.map_err(|reason| Error::InvalidDefault {
+                        node: node.id.clone(),
+                        reason,
+                    })?;
+                    call.outcome = Outcome::Defaulted;
+                    Ok(call)
+                }
+                None if node.on_error == OnError::Skip => Ok(call),
+                None => Err(err),
+            };
+        }
+    }
+
+    /// Load and compile a node's module and check it against its sandbox.
+    fn prepare(
+        &self,
+        base_dir: &Path,
+        node: &NodeSpec,
+        limits: Limits,
+        sandbox: Sandbox,
+    ) -> Result<Prepared> {
+        let wasm_path = base_dir.join(&node.wasm);
+        let wasm_binary = fs::read(&wasm_path).map_err(|err| Error::io(&wasm_path, err))?;
+        let engine = self.engines.get(sandbox.max_stack_bytes);
+        let wasm_hash = content_hash(&wasm_binary);
+        if !self.allow_stale {
+            if let Some(reason) = self.manifests.stale_reason(&wasm_path, &wasm_hash)? {
+                return Err(Error::StaleArtifact {
+                    node: node.id.clone(),
+                    path: wasm_path,
+                    reason,
+                });
+            }
+        }
+        let module = self.cache.module(
+            &engine,
+            sandbox.max_stack_bytes,
+            &node.id,
+            &wasm_binary,
+            &wasm_hash,
+        )?;
+        let (abi, imports) = match &module {
+            Compiled::Module(module) => (
+                AbiVersion::detect(module).ok_or_else(|| Error::MissingEntry(node.id.clone()))?,
+                sandbox.check_imports(&node.id, module)?,
+            ),
+            Compiled::Component(component) => (
+                AbiVersion::Component,
+                component::check_imports(&sandbox, &node.id, &engine, component)?,
+            ),
+        };
+        Ok(Prepared {
+            engine,
+            module,
+            wasm_hash,
+            abi,
+            imports,
+            limits,
+            sandbox,
+        })
+    }
+
+    /// Instantiate a prepared node and invoke it once, returning its inputs
+    /// and outputs as recorded. Lines the node logs are appended to `logs`
+    /// whether or not it succeeds.
+    fn invoke(
+        &self,
+        base_dir: &Path,
+        node: &NodeSpec,
+        inputs: PortValues,
+        prepared: &Prepared,
+        logs: &mut Vec<LogLine>,
+    ) -> Result<(PortValues, PortValues)> {
+        let Prepared {
+            engine,
+            module,
+            abi,
+            imports,
+            limits,
+            sandbox,
+            ..
+        } = prepared;
+        let wasi = if imports.wasi {
+            Some(sandbox.wasi_ctx(base_dir)?)
+        } else {
+            None
+        };
+        let state = NodeState {
+            limiter: sandbox.limiter(),
+            wasi,
+            log: NodeLog::new(&node.id, self.echo_logs),
+        };
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limiter);
+        limits.apply(&mut store, &node.id)?;
+
+        let result = match module {
+            Compiled::Module(module) => {
+                run_instance(&mut store, *imports, module, node, *abi, inputs)
+            }
+            Compiled::Component(component) => {
+                component::run(&mut store, component, *imports, node, inputs)
+            }
+        };
+        logs.append(&mut store.data_mut().log.lines);
+        match (result, store.data_mut().limiter.exceeded.take()) {
+            (Err(_), Some(exceeded)) => Err(Error::SandboxLimit {
+                node: node.id.clone(),
+                exceeded,
+            }),
+            (result, _) => result,
+        }
+    }
+
+    /// Run the full graph and return the execution trace for inspection.
+    ///
+    /// Nodes whose dependencies have all finished run concurrently, up to
+    /// [`Runtime::jobs`] at a time.
+    pub fn run_graph(&self, graph_path: impl AsRef<Path>) -> Result<ExecutionState> {
+        match self.run_graph_partial(graph_path) {
+            (_, Some(err)) => Err(err),
+            (state, None) => Ok(state),
+        }
+    }
+
+    /// Like [`Runtime::run_graph`], but a failed run still returns the
+    /// records of the nodes that finished, e.g. to write its trace.
+    pub fn run_graph_partial(
+        &self,
+        graph_path: impl AsRef<Path>,
+    ) -> (ExecutionState, Option<Error>) {
+        match self.run_spec(graph_path.as_ref()) {
+            Ok((_, state, failure)) => (state, failure),
+            Err(err) => (ExecutionState::new(), Some(err)),
+        }
+    }
+
+    /// Run the graph as a function: bind `inputs` to its graph inputs and
+    /// return its graph outputs, keyed by output name.
+    pub fn call(
+        &self,
+        graph_path: impl AsRef<Path>,
+        inputs: Map<String, Value>,
+    ) -> Result<PortValues> {
+        let runtime = self.clone().with_inputs(inputs);
+        match runtime.run_spec(graph_path.as_ref())? {
+            (_, _, Some(err)) => Err(err),
+            (graph, state, None) => Ok(graph_outputs(&graph, &state)),
+        }
+    }
+
+    /// Validate, order and run the graph. Errors that stop the graph from
+    /// starting are returned as such; a failed run returns its state too.
+    fn run_spec(&self, graph_path: &Path) -> Result<(GraphSpec, ExecutionState, Option<Error>)> {
+        let graph = validate_graph(graph_path)?;
+        let graph_inputs = graph.bind_inputs(&self.inputs)?;
+        let ordered = topo_sort(&graph)?;
+        let base_dir = graph_path.parent().unwrap_or_else(|| Path::new("."));
+        let runtime = self.for_run();
+        let (state, failure) = scheduler::run(&runtime, base_dir, &graph, &ordered, &graph_inputs);
+        Ok((graph, state, failure))
+    }
+
+    /// The runtime for one run: it shares the caches, but checks modules
+    /// against their build manifests afresh.
+    fn for_run(&self) -> Runtime {
+        Runtime {
+            manifests: Arc::default(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Instantiate `module` and call it through `abi`, returning the inputs and
+/// outputs as recorded.
+fn run_instance(
+    store: &mut Store<NodeState>,
+    imports: Imports,
+    module: &Module,
+    node: &NodeSpec,
+    abi: AbiVersion,
+    inputs: PortValues,
+) -> Result<(PortValues, PortValues)> {
+    // Only imports the policy allows are linked; a node without a policy
+    // gets none, like the empty import object of the prototype.
+    let mut linker = Linker::new(store.engine());
+    if imports.log {
+        log::add_to_linker(&mut linker).map_err(|err| Error::wasm(&node.id, err))?;
+    }
+    if imports.wasi {
+        p1::add_to_linker_sync(&mut linker, |state: &mut NodeState| {
+            state
+                .wasi
+                .as_mut()
+                .expect("WASI is linked only with a context")
+        })
+        .map_err(|err| Error::wasm(&node.id, err))?;
+    }
+    let instance = linker
+        .instantiate(&mut *store, module)
+        .map_err(|err| Error::wasm(&node.id, err))?;
+    let config = node.config.as_ref().map(|_| node.config_json());
+    abi::call_init(&mut *store, &instance, &node.id, abi, config.as_deref())?;
+
+    let (inputs, outputs) = match abi {
+        AbiVersion::V1 => {
+            let (input_port, output_port) = v1_ports(node)?;
+            let mut recorded = PortValues::new();
+            let mut value = 0;
+            if let Some((port, payload)) = input_port.and_then(|port| inputs.get_key_value(&port)) {
+                value = payload.to_int().ok_or_else(|| Error::IncompatibleInput {
+                    node: node.id.clone(),
+                    input: payload.clone(),
+                })?;
+                recorded.insert(port.clone(), Payload::Int(value));
+            }
+            let output = abi::call_v1(&mut *store, &instance, &node.id, value)?;
+            (
+                recorded,
+                PortValues::from([(output_port, Payload::Int(output))]),
+            )
+        }
+        AbiVersion::V2 if node.has_ports() => {
+            let envelope = encode_envelope(node, &inputs)?;
+            let output = abi::call_v2(&mut *store, &instance, &node.id, &envelope)?;
+            (inputs, decode_envelope(node, &output)?)
+        }
+        AbiVersion::V2 => {
+            let bytes = inputs
+                .get(DEFAULT_INPUT)
+                .cloned()
+                .unwrap_or(Payload::Int(0))
+                .into_bytes();
+            let output = abi::call_v2(&mut *store, &instance, &node.id, &bytes)?;
+            (
+                PortValues::from([(DEFAULT_INPUT.to_string(), Payload::Bytes(bytes))]),
+                PortValues::from([(DEFAULT_OUTPUT.to_string(), Payload::Bytes(output))]),
+            )
+        }
+        AbiVersion::Component => unreachable!("components are not core modules"),
+    };
+    Ok((inputs, outputs))
+}
+
+/// Run the graph at `graph_path` with a freshly configured [`Runtime`].
+pub fn run_graph(graph_path: impl AsRef<Path>) -> Result<ExecutionState> {
+    Runtime::new().run_graph(graph_path)
+}
@@ -0,0 +1,321 @@
+//! Graphviz and Mermaid renderings of a graph.
+//!
+//! Each node is drawn with its id, wasm path and declared ports. Edges
+//! between ports are labelled with the port names; `dependsOn` entries
+//! without an edge are drawn dashed, except the one feeding a port-less
+//! node's implicit input. Given a [`RunTrace`], nodes are annotated with the
+//! inputs, outputs and duration the trace recorded and coloured by outcome.
+
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::failure::Outcome;
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::ports::PortSpec;
+use crate::trace::RunTrace;
+
+/// Output format of [`export_graph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "dot" => Ok(ExportFormat::Dot),
+            "mermaid" => Ok(ExportFormat::Mermaid),
+            _ => Err(format!(
+                "unknown export format {value:?}; expected dot or mermaid"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Mermaid => "mermaid",
+        })
+    }
+}
+
+/// How a traced node is coloured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    /// The node the run failed on.
+    Failed,
+    /// The node failed and its `onError` policy handled it.
+    Handled,
+    /// The node was skipped because an upstream node was.
+    Skipped,
+}
+
+impl Status {
+    fn class(self) -> &'static str {
+        match self {
+            Status::Failed => "failed",
+            Status::Handled => "handled",
+            Status::Skipped => "skipped",
+        }
+    }
+
+    fn fill(self) -> &'static str {
+        match self {
+            Status::Failed => "#f8d7da",
+            Status::Handled => "#fff3cd",
+            Status::Skipped => "#e2e3e5",
+        }
+    }
+
+    fn stroke(self) -> &'static str {
+        match self {
+            Status::Failed => "#c0392b",
+            Status::Handled => "#d68910",
+            Status::Skipped => "#7f8c8d",
+        }
+    }
+}
+
+/// A connection to draw between two nodes.
+struct Link<'a> {
+    from: &'a str,
+    to: &'a str,
+    label: Option<String>,
+    ordering_only: bool,
+}
+
+/// Render `graph` in `format`, annotated with `trace` if given.
+pub fn export_graph(graph: &GraphSpec, format: ExportFormat, trace: Option<&RunTrace>) -> String {
+    match format {
+        ExportFormat::Dot => format_dot(graph, trace),
+        ExportFormat::Mermaid => format_mermaid(graph, trace),
+    }
+}
+
+fn format_dot(graph: &GraphSpec, trace: Option<&RunTrace>) -> String {
+    let mut out = String::from("digraph G {\n    rankdir=LR;\n");
+    out.push_str("    node [shape=box, style=rounded, fontname=\"Helvetica\"];\n");
+    out.push_str("    edge [fontname=\"Helvetica\", fontsize=10];\n");
+    for node in &graph.nodes {
+        let label = node_lines(node, trace).join("\n");
+        let _ = write!(
+            out,
+            "    {} [label={}",
+            dot_string(&node.id),
+            dot_string(&label)
+        );
+        if let Some(status) = status(node, trace) {
+            let _ = write!(
+                out,
+                ", style=\"rounded,filled\", fillcolor=\"{}\", color=\"{}\"",
+                status.fill(),
+                status.stroke()
+            );
+        }
+        out.push_str("];\n");
+    }
+    for link in links(graph) {
+        let _ = write!(
+            out,
+            "    {} -> {}",
+            dot_string(link.from),
+            dot_string(link.to)
+        );
+        let mut attributes = Vec::new();
+        if let Some(label) = &link.label {
+            attributes.push(format!("label={}", dot_string(label)));
+        }
+        if link.ordering_only {
+            attributes.push("style=dashed".to_string());
+        }
+        if !attributes.is_empty() {
+            let _ = write!(out, " [{}]", attributes.join(", "));
+        }
+        out.push_str(";\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn format_mermaid(graph: &GraphSpec, trace: Option<&RunTrace>) -> String {
+    // Node ids may contain characters Mermaid does not accept in ids, so
+    // nodes are referred to by index and labelled with their id.
+    let key = |id: &str| {
+        let index = graph.nodes.iter().position(|node| node.id == id);
+        format!("n{}", index.expect("links only join declared nodes"))
+    };
+    let mut out = String::from("flowchart LR\n");
+    let mut classes: Vec<(Status, Vec<String>)> = Vec::new();
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let label = node_lines(node, trace)
+            .iter()
+            .map(|line| mermaid_text(line))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        let _ = writeln!(out, "    n{index}[\"{label}\"]");
+        if let Some(status) = status(node, trace) {
+            match classes.iter_mut().find(|(known, _)| *known == status) {
+                Some((_, members)) => members.push(format!("n{index}")),
+                None => classes.push((status, vec![format!("n{index}")])),
+            }
+        }
+    }
+    for link in links(graph) {
+        let arrow = match (&link.label, link.ordering_only) {
+            (Some(label), false) => format!("-- \"{}\" -->", mermaid_text(label)),
+            (Some(label), true) => format!("-. \"{}\" .->", mermaid_text(label)),
+            (None, false) => "-->".to_string(),
+            (None, true) => "-.->".to_string(),
+        };
+        let _ = writeln!(out, "    {} {arrow} {}", key(link.from), key(link.to));
+    }
+    for (status, members) in classes {
+        let _ = writeln!(
+            out,
+            "    classDef {} fill:{},stroke:{}",
+            status.class(),
+            status.fill(),
+            status.stroke()
+        );
+        let _ = writeln!(out, "    class {} {}", members.join(","), status.class());
+    }
+    out
+}
+
+/// The lines of a node's label: id, wasm path, declared ports and, when
+/// traced, what the node received and produced and how long it took.
+fn node_lines(node: &NodeSpec, trace: Option<&RunTrace>) -> Vec<String> {
+    let mut lines = vec![node.id.clone(), node.wasm.clone()];
+    if !node.inputs.is_empty() {
+        lines.push(format!("in: {}", port_list(&node.inputs)));
+    }
+    if !node.outputs.is_empty() {
+        lines.push(format!("out: {}", port_list(&node.outputs)));
+    }
+    let Some(trace) = trace else {
+        return lines;
+    };
+    if let Some(event) = trace.nodes.get(&node.id) {
+        if event.outcome == Outcome::Skipped {
+            lines.push("skipped".to_string());
+        } else {
+            lines.push(format!(
+                "{} -> {}",
+                value_list(&event.inputs),
+                value_list(&event.outputs)
+            ));
+        }
+        lines.push(format!("{:.3} ms", event.duration().as_secs_f64() * 1000.0));
+        if let Some(error) = &event.error {
+            lines.push(error.message.clone());
+        }
+    } else if let Some(failure) = trace
+        .failure
+        .as_ref()
+        .filter(|failure| failure.node.as_deref() == Some(node.id.as_str()))
+    {
+        lines.push(failure.message.clone());
+    }
+    lines
+}
+
+fn status(node: &NodeSpec, trace: Option<&RunTrace>) -> Option<Status> {
+    let trace = trace?;
+    if let Some(event) = trace.nodes.get(&node.id) {
+        return match (event.outcome, &event.error) {
+            (Outcome::Succeeded, _) => None,
+            (_, Some(_)) => Some(Status::Handled),
+            (_, None) => Some(Status::Skipped),
+        };
+    }
+    let failure = trace.failure.as_ref()?;
+    (failure.node.as_deref() == Some(node.id.as_str())).then_some(Status::Failed)
+}
+
+/// Every edge, labelled with its ports, followed by the `dependsOn` entries
+/// no edge already covers.
+fn links(graph: &GraphSpec) -> Vec<Link<'_>> {
+    let mut links: Vec<Link> = graph
+        .edges
+        .iter()
+        .map(|edge| Link {
+            from: &edge.from.node,
+            to: &edge.to.node,
+            label: Some(if edge.from.port == edge.to.port {
+                edge.from.port.clone()
+            } else {
+                format!("{} -> {}", edge.from.port, edge.to.port)
+            }),
+            ordering_only: false,
+        })
+        .collect();
+    for node in &graph.nodes {
+        for (index, dependency) in node.depends_on.iter().enumerate() {
+            if graph
+                .incoming_edges(&node.id)
+                .any(|edge| edge.from.node == *dependency)
+            {
+                continue;
+            }
+            // Without ports or edges, the last dependency feeds the node.
+            let feeds = !node.has_ports()
+                && graph.incoming_edges(&node.id).next().is_none()
+                && index + 1 == node.depends_on.len();
+            links.push(Link {
+                from: dependency,
+                to: &node.id,
+                label: None,
+                ordering_only: !feeds,
+            });
+        }
+    }
+    links
+}
+
+fn port_list(ports: &[PortSpec]) -> String {
+    ports
+        .iter()
+        .map(|port| format!("{}: {}", port.name, port.ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn value_list(values: &Map<String, Value>) -> String {
+    let values: Vec<String> = values
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    format!("{{{}}}", values.join(", "))
+}
+
+/// A double-quoted DOT string, with line breaks kept.
+fn dot_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Text safe inside a quoted Mermaid label.
+fn mermaid_text(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
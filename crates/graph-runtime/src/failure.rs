@@ -0,0 +1,300 @@
+//! Structured node failures and the per-node `onError` policy.
+//!
+//! A node that traps, reports an error of its own, runs out of fuel or time,
+//! or hits its sandbox caps is described by a [`NodeError`]: what went
+//! wrong, where in the guest it happened and which inputs triggered it. Its
+//! `onError` policy then decides whether that fails the run, skips the node
+//! (and everything downstream of it), substitutes default outputs or
+//! retries the node.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasmtime::{Trap, WasmBacktrace};
+
+use crate::error::Error;
+use crate::graph::NodeSpec;
+use crate::ports::PortValues;
+use crate::report::format_values;
+
+/// What a node failure was caused by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureKind {
+    /// An `unreachable` instruction, which is also how Rust guests panic.
+    Unreachable,
+    DivideByZero,
+    IntegerOverflow,
+    /// A float-to-integer conversion of NaN or an out-of-range value.
+    BadConversion,
+    MemoryOutOfBounds,
+    TableOutOfBounds,
+    /// An indirect call through a null table entry.
+    NullCall,
+    /// An indirect call to a function of the wrong type.
+    SignatureMismatch,
+    StackOverflow,
+    /// Any other trap.
+    Trap,
+    /// An error the node reported through the ABI rather than a trap.
+    Reported,
+    OutOfFuel,
+    Timeout,
+    SandboxLimit,
+}
+
+impl FailureKind {
+    fn from_trap(trap: Trap) -> FailureKind {
+        match trap {
+            Trap::UnreachableCodeReached => FailureKind::Unreachable,
+            Trap::IntegerDivisionByZero => FailureKind::DivideByZero,
+            Trap::IntegerOverflow => FailureKind::IntegerOverflow,
+            Trap::BadConversionToInteger => FailureKind::BadConversion,
+            Trap::MemoryOutOfBounds | Trap::HeapMisaligned => FailureKind::MemoryOutOfBounds,
+            Trap::TableOutOfBounds => FailureKind::TableOutOfBounds,
+            Trap::IndirectCallToNull => FailureKind::NullCall,
+            Trap::BadSignature => FailureKind::SignatureMismatch,
+            Trap::StackOverflow => FailureKind::StackOverflow,
+            Trap::OutOfFuel => FailureKind::OutOfFuel,
+            Trap::Interrupt => FailureKind::Timeout,
+            _ => FailureKind::Trap,
+        }
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FailureKind::Unreachable => "unreachable code reached",
+            FailureKind::DivideByZero => "integer divide by zero",
+            FailureKind::IntegerOverflow => "integer overflow",
+            FailureKind::BadConversion => "invalid conversion to integer",
+            FailureKind::MemoryOutOfBounds => "out of bounds memory access",
+            FailureKind::TableOutOfBounds => "out of bounds table access",
+            FailureKind::NullCall => "indirect call to null",
+            FailureKind::SignatureMismatch => "indirect call type mismatch",
+            FailureKind::StackOverflow => "call stack exhausted",
+            FailureKind::Trap => "trap",
+            FailureKind::Reported => "reported error",
+            FailureKind::OutOfFuel => "out of fuel",
+            FailureKind::Timeout => "timeout",
+            FailureKind::SandboxLimit => "sandbox limit exceeded",
+        })
+    }
+}
+
+/// A guest stack frame, innermost first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Function name from the module's name section, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// Index of the function in the module.
+    pub index: u32,
+    /// Byte offset of the instruction in the module, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(name) => write!(f, "{name}")?,
+            None => write!(f, "<wasm function {}>", self.index)?,
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " @ {offset:#x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A node failure the node's `onError` policy can handle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeError {
+    pub node: String,
+    pub kind: FailureKind,
+    /// The code of an error the node reported itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i32>,
+    /// The failure as reported to the user.
+    pub message: String,
+    /// The inputs the failing attempt received.
+    pub inputs: PortValues,
+    /// Guest stack at the point of a trap; empty for other failures.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backtrace: Vec<Frame>,
+}
+
+impl NodeError {
+    /// Describe a trap raised while running `node`.
+    pub(crate) fn from_trap(
+        node: &str,
+        trap: Trap,
+        source: &wasmtime::Error,
+        inputs: &PortValues,
+    ) -> NodeError {
+        let kind = FailureKind::from_trap(trap);
+        let backtrace = source
+            .downcast_ref::<WasmBacktrace>()
+            .map(|backtrace| {
+                backtrace
+                    .frames()
+                    .iter()
+                    .map(|frame| Frame {
+                        function: frame.func_name().map(str::to_string),
+                        index: frame.func_index(),
+                        offset: frame.module_offset(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        NodeError {
+            node: node.to_string(),
+            kind,
+            code: None,
+            message: format!(
+                "Node {node} trapped: {kind} with inputs {}.",
+                format_values(inputs)
+            ),
+            inputs: inputs.clone(),
+            backtrace,
+        }
+    }
+
+    /// The failure behind `err`, if `err` is one `onError` applies to: a
+    /// trap, an error the node reported, an exhausted budget or an exceeded
+    /// sandbox cap. Configuration problems such as a missing export always
+    /// fail the run.
+    pub fn from_error(err: &Error, inputs: &PortValues) -> Option<NodeError> {
+        let (node, kind, code) = match err {
+            Error::Trap(failure) => return Some((**failure).clone()),
+            Error::NodeReported { node, code, .. } => (node, FailureKind::Reported, Some(*code)),
+            Error::FuelExhausted { node, .. } => (node, FailureKind::OutOfFuel, None),
+            Error::NodeTimedOut { node, .. } => (node, FailureKind::Timeout, None),
+            Error::SandboxLimit { node, .. } => (node, FailureKind::SandboxLimit, None),
+            _ => return None,
+        };
+        Some(NodeError {
+            node: node.clone(),
+            kind,
+            code,
+            message: err.to_string(),
+            inputs: inputs.clone(),
+            backtrace: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        // Deep recursion would otherwise print thousands of identical lines.
+        for run in self.backtrace.chunk_by(|a, b| a == b) {
+            write!(f, "\n    at {}", run[0])?;
+            if run.len() > 1 {
+                write!(f, " ({} times)", run.len())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NodeError {}
+
+/// Turn a trap `limits` did not claim into an [`Error::Trap`].
+pub(crate) fn classify(node: &str, err: Error, inputs: &PortValues) -> Error {
+    let Error::Wasm { source, .. } = &err else {
+        return err;
+    };
+    match source.downcast_ref::<Trap>() {
+        Some(&trap) => Error::Trap(Box::new(NodeError::from_trap(node, trap, source, inputs))),
+        None => err,
+    }
+}
+
+/// What to do when a node fails.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// Fail the whole run.
+    #[default]
+    Fail,
+    /// Record the node, and every node depending on it, as skipped.
+    Skip,
+    /// Record these outputs instead: the value of the only output port, or
+    /// an object keyed by output port when the node declares several.
+    Default(Value),
+    /// Run the node up to this many more times before failing the run.
+    Retry(u32),
+}
+
+impl OnError {
+    pub fn is_fail(&self) -> bool {
+        matches!(self, OnError::Fail)
+    }
+
+    /// How many times a node may run in total.
+    pub fn attempts(&self) -> u32 {
+        match self {
+            OnError::Retry(retries) => retries.saturating_add(1),
+            _ => 1,
+        }
+    }
+
+    /// The outputs a `default` policy substitutes for `node`, or why its
+    /// value does not fit the node's output ports.
+    pub fn default_outputs(&self, node: &NodeSpec) -> Option<Result<PortValues, String>> {
+        let OnError::Default(value) = self else {
+            return None;
+        };
+        let ports = node.output_ports();
+        let values: Vec<(String, Value)> = match ports.as_slice() {
+            [port] => vec![(port.name.clone(), value.clone())],
+            _ => {
+                let Some(object) = value.as_object() else {
+                    return Some(Err(
+                        "a node with several outputs needs an object keyed by port".to_string(),
+                    ));
+                };
+                let mut values = Vec::new();
+                for port in &ports {
+                    match object.get(&port.name) {
+                        Some(value) => values.push((port.name.clone(), value.clone())),
+                        None => return Some(Err(format!("missing output port {}", port.name))),
+                    }
+                }
+                values
+            }
+        };
+        let mut outputs = PortValues::new();
+        for ((name, value), port) in values.into_iter().zip(&ports) {
+            match port.ty.decode(value) {
+                Some(payload) => {
+                    outputs.insert(name, payload);
+                }
+                None => {
+                    return Some(Err(format!(
+                        "output port {name} is not a valid {}",
+                        port.ty
+                    )))
+                }
+            }
+        }
+        Some(Ok(outputs))
+    }
+}
+
+/// How a node's execution ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// The node produced its outputs, possibly after retries.
+    #[default]
+    Succeeded,
+    /// The node failed and its `default` outputs were recorded instead.
+    Defaulted,
+    /// The node failed under a `skip` policy, or depends on a skipped node.
+    Skipped,
+}
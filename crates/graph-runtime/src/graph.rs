@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::Result;
+use crate::failure::OnError;
+use crate::interface::{GraphInput, GraphOutput};
+use crate::limits::Limits;
+use crate::ports::{EdgeSpec, PortSpec};
+use crate::sandbox::Sandbox;
+use crate::template::ReportTemplate;
+use crate::validate;
+
+/// A single node entry in `graph.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSpec {
+    pub id: String,
+    /// Path to the compiled module, relative to the graph file.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub wasm: String,
+    /// Path to a graph file this node embeds instead of running a module,
+    /// relative to the graph file. Loading a graph flattens such nodes away;
+    /// see [`crate::subgraph`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph: Option<String>,
+    /// Ordering-only dependencies. Without edges, the last entry also
+    /// feeds the node's implicit input port.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<PortSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<PortSpec>,
+    /// Execution budgets for this node, overriding the graph's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+    /// Sandbox policy for this node, overriding the graph's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<Sandbox>,
+    /// What to do when the node fails.
+    #[serde(default, skip_serializing_if = "OnError::is_fail")]
+    pub on_error: OnError,
+    /// Whether the node's outputs depend only on its module and inputs, so
+    /// they may be served from the result cache.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pure: bool,
+    /// Static configuration handed to the node before it runs, so one
+    /// module can be reused with different parameters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Map<String, Value>>,
+}
+
+impl NodeSpec {
+    /// The JSON text the node receives as its configuration: its `config`
+    /// object, or `null` when it has none.
+    pub(crate) fn config_json(&self) -> String {
+        match &self.config {
+            Some(config) => Value::Object(config.clone()).to_string(),
+            None => Value::Null.to_string(),
+        }
+    }
+}
+
+/// The full graph description.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSpec {
+    pub nodes: Vec<NodeSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edges: Vec<EdgeSpec>,
+    /// Values the caller passes in, wired to node input ports.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<GraphInput>,
+    /// The graph's named results, taken from node output ports.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<GraphOutput>,
+    /// Default execution budgets for every node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+    /// Default sandbox policy for every node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<Sandbox>,
+    /// The summary line printed after a run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<ReportTemplate>,
+}
+
+/// Load the graph specification from disk, rejecting documents that do not
+/// match the schema or whose edges do not line up with the declared ports.
+/// Subgraph nodes are flattened into the nodes of the graphs they embed.
+///
+/// Unlike [`validate_graph`](crate::validate_graph) this does not check that
+/// the referenced wasm files exist.
+pub fn load_graph(graph_path: impl AsRef<Path>) -> Result<GraphSpec> {
+    validate::load(graph_path.as_ref(), false)
+}
@@ -0,0 +1,102 @@
+//! Graph-level inputs and outputs, which make a graph callable like a
+//! function.
+//!
+//! A graph declares typed `inputs` that the caller supplies when running it
+//! ([`Runtime::with_inputs`](crate::Runtime::with_inputs), or `--input` and
+//! `--inputs` on the command line), each wired to one or more node input
+//! ports, and named `outputs` that pick the graph's results from node
+//! output ports:
+//!
+//! ```json
+//! {
+//!   "inputs": [{ "name": "userId", "type": "i32", "to": ["fetchUser.userId"] }],
+//!   "outputs": [{ "name": "score", "from": "renderProfile.score" }]
+//! }
+//! ```
+//!
+//! An input with a `default` may be left out; any other input is required.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+use crate::executor::ExecutionState;
+use crate::graph::GraphSpec;
+use crate::payload::Payload;
+use crate::ports::{PortRef, PortType, PortValues};
+
+/// A typed value the caller passes into the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphInput {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub ty: PortType,
+    /// The node input ports the value feeds.
+    pub to: Vec<PortRef>,
+    /// The value used when the caller supplies none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+/// A named result of the graph, taken from a node output port.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphOutput {
+    pub name: String,
+    pub from: PortRef,
+}
+
+impl GraphSpec {
+    /// The graph input that feeds `port`, if any.
+    pub fn input_feeding(&self, port: &PortRef) -> Option<&GraphInput> {
+        self.inputs.iter().find(|input| input.to.contains(port))
+    }
+
+    /// Decode the values `supplied` for the graph's inputs, keyed by input
+    /// name, falling back to each input's `default`.
+    pub fn bind_inputs(&self, supplied: &Map<String, Value>) -> Result<PortValues> {
+        if let Some(name) = supplied
+            .keys()
+            .find(|name| !self.inputs.iter().any(|input| &input.name == *name))
+        {
+            return Err(Error::UnknownGraphInput(name.clone()));
+        }
+        let mut values = PortValues::new();
+        for input in &self.inputs {
+            let value = supplied
+                .get(&input.name)
+                .or(input.default.as_ref())
+                .ok_or_else(|| Error::MissingGraphInput(input.name.clone()))?;
+            values.insert(input.name.clone(), input.decode(value)?);
+        }
+        Ok(values)
+    }
+}
+
+impl GraphInput {
+    /// Convert a JSON value to the input's type.
+    pub(crate) fn decode(&self, value: &Value) -> Result<Payload> {
+        self.ty
+            .decode(value.clone())
+            .ok_or_else(|| Error::GraphInputValue {
+                input: self.name.clone(),
+                expected: self.ty,
+                value: value.clone(),
+            })
+    }
+}
+
+/// The graph's outputs, keyed by output name, as produced in `state`.
+/// Outputs whose node did not run or was skipped are left out.
+pub fn graph_outputs(graph: &GraphSpec, state: &ExecutionState) -> PortValues {
+    graph
+        .outputs
+        .iter()
+        .filter_map(|output| {
+            let value = state
+                .get(&output.from.node)?
+                .outputs
+                .get(&output.from.port)?;
+            Some((output.name.clone(), value.clone()))
+        })
+        .collect()
+}
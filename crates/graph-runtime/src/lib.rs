@@ -0,0 +1,66 @@
+//! Native host runtime for the graph prototype.
+//!
+//! The runtime consumes a declarative graph description (`graph.json`),
+//! resolves execution order via topological sorting, and executes
+//! WebAssembly nodes that exchange integer or byte payloads. It mirrors the
+//! behaviour of the original TypeScript orchestrator so the pipeline can be
+//! driven end to end from Rust.
+
+mod abi;
+mod build;
+mod cache;
+pub mod cli;
+mod component;
+mod conformance;
+mod error;
+mod executor;
+mod export;
+mod failure;
+mod graph;
+mod interface;
+mod limits;
+mod locate;
+mod log;
+mod memo;
+mod payload;
+mod ports;
+mod report;
+mod sandbox;
+mod scheduler;
+mod subgraph;
+mod template;
+mod topo;
+mod trace;
+mod validate;
+
+pub use abi::AbiVersion;
+pub use build::{
+    source_hash, BuildManifest, BuiltArtifact, BuiltNode, NodeBuild, NodePackage, BUILD_MANIFEST,
+    NODE_TARGET,
+};
+pub use cache::{default_cache_dir, CacheStats};
+pub use component::{componentize, NODE_WIT, NODE_WORLD};
+pub use conformance::{check_node, Conformance};
+pub use error::{Error, Result};
+pub use executor::{resolve_inputs, run_graph, ExecutionRecord, ExecutionState, Runtime};
+pub use export::{export_graph, ExportFormat};
+pub use failure::{FailureKind, Frame, NodeError, OnError, Outcome};
+pub use graph::{load_graph, GraphSpec, NodeSpec};
+pub use interface::{graph_outputs, GraphInput, GraphOutput};
+pub use limits::{Limits, DEFAULT_TIMEOUT};
+pub use locate::Position;
+pub use log::{LogLevel, LogLine};
+pub use memo::MemoStats;
+pub use payload::Payload;
+pub use ports::{
+    EdgeSpec, PortDirection, PortRef, PortSpec, PortType, PortValues, DEFAULT_INPUT, DEFAULT_OUTPUT,
+};
+pub use report::{format_report, format_state, format_timings, log_state};
+pub use sandbox::{Capability, Exceeded, Preopen, Sandbox, MAX_STACK_BYTES, WASM_PAGE_SIZE};
+pub use scheduler::Timing;
+pub use template::ReportTemplate;
+pub use topo::{find_cycles, topo_sort, Cycle, CycleEdge};
+pub use trace::{
+    format_chrome_trace, format_trace, read_trace, NodeEvent, RunTrace, TraceEvent, TracedError,
+};
+pub use validate::{validate_graph, Diagnostic, ValidationReport, GRAPH_SCHEMA};
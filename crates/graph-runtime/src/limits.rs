@@ -0,0 +1,104 @@
+//! Per-node execution budgets.
+//!
+//! Every node runs with an instruction fuel budget and a wall-clock timeout
+//! so a buggy node traps instead of hanging the run. Budgets are declared as
+//! `limits` on a node or on the whole graph; node values win over graph
+//! values, which win over the runtime's defaults.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{Engine, Store, Trap, UpdateDeadline};
+
+use crate::error::{Error, Result};
+use crate::graph::{GraphSpec, NodeSpec};
+
+/// Wall-clock timeout applied when neither the node, the graph nor the
+/// runtime configures one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the engine epoch advances; the granularity of timeouts.
+const TICK: Duration = Duration::from_millis(5);
+
+/// Execution budgets. Unset fields fall back to the next level up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Limits {
+    /// Instruction fuel; unlimited when unset everywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<u64>,
+    /// Wall-clock timeout in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl Limits {
+    /// Fill unset fields from `fallback`.
+    pub fn or(self, fallback: Limits) -> Limits {
+        Limits {
+            fuel: self.fuel.or(fallback.fuel),
+            timeout_ms: self.timeout_ms.or(fallback.timeout_ms),
+        }
+    }
+
+    /// The budgets that apply to `node`.
+    pub fn for_node(graph: &GraphSpec, node: &NodeSpec, defaults: Limits) -> Limits {
+        node.limits
+            .unwrap_or_default()
+            .or(graph.limits.unwrap_or_default())
+            .or(defaults)
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Arm `store` with these budgets, starting the clock now.
+    pub(crate) fn apply<T>(&self, store: &mut Store<T>, node: &str) -> Result<()> {
+        store
+            .set_fuel(self.fuel.unwrap_or(u64::MAX))
+            .map_err(|err| Error::wasm(node, err))?;
+        let deadline = self.timeout().map(|timeout| Instant::now() + timeout);
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| match deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Trap::Interrupt.into()),
+            _ => Ok(UpdateDeadline::Continue(1)),
+        });
+        Ok(())
+    }
+
+    /// Turn a trap caused by an exhausted budget into the matching error.
+    pub(crate) fn classify(&self, node: &str, err: Error) -> Error {
+        let Error::Wasm { source, .. } = &err else {
+            return err;
+        };
+        match source.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => Error::FuelExhausted {
+                node: node.to_string(),
+                fuel: self.fuel.unwrap_or(u64::MAX),
+            },
+            Some(Trap::Interrupt) => Error::NodeTimedOut {
+                node: node.to_string(),
+                timeout: self.timeout().unwrap_or_default(),
+            },
+            _ => err,
+        }
+    }
+}
+
+/// Advance the epoch of `engine` every [`TICK`] for as long as the engine
+/// is alive, so epoch deadlines are checked regularly.
+pub(crate) fn start_ticker(engine: &Engine) {
+    let weak = engine.weak();
+    thread::Builder::new()
+        .name("graph-runtime-epoch".to_string())
+        .spawn(move || loop {
+            thread::sleep(TICK);
+            match weak.upgrade() {
+                Some(engine) => engine.increment_epoch(),
+                None => return,
+            }
+        })
+        .expect("failed to spawn the epoch ticker");
+}
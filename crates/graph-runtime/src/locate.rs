@@ -0,0 +1,171 @@
+//! Source positions for JSON values, keyed by JSON pointer.
+//!
+//! `serde_json::Value` does not remember where anything came from, so the
+//! validator re-scans the (already syntax-checked) document and records the
+//! line and column of every value and object key.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A 1-based line and column in a source file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Positions of the values and object keys of a JSON document.
+#[derive(Debug, Default)]
+pub(crate) struct SourceMap {
+    values: HashMap<String, Position>,
+    keys: HashMap<String, Position>,
+}
+
+impl SourceMap {
+    /// Index a syntactically valid JSON document. Malformed input yields a
+    /// partial map rather than an error.
+    pub(crate) fn new(text: &str) -> Self {
+        let mut scanner = Scanner {
+            chars: text.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+            map: SourceMap::default(),
+        };
+        scanner.value(String::new());
+        scanner.map
+    }
+
+    /// Where the value at `pointer` starts.
+    pub(crate) fn value(&self, pointer: &str) -> Option<Position> {
+        self.values.get(pointer).copied()
+    }
+
+    /// Where the object key naming the member at `pointer` starts, falling
+    /// back to the value itself (e.g. for array items).
+    pub(crate) fn key(&self, pointer: &str) -> Option<Position> {
+        self.keys
+            .get(pointer)
+            .or_else(|| self.values.get(pointer))
+            .copied()
+    }
+}
+
+/// Escape a key for use as a JSON pointer segment.
+pub(crate) fn escape_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+struct Scanner<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    position: Position,
+    map: SourceMap,
+}
+
+impl Scanner<'_> {
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn value(&mut self, pointer: String) {
+        self.skip_whitespace();
+        self.map.values.insert(pointer.clone(), self.position);
+        match self.chars.peek() {
+            Some('{') => self.object(&pointer),
+            Some('[') => self.array(&pointer),
+            Some('"') => {
+                self.string();
+            }
+            Some(_) => {
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ',' | ']' | '}') && !c.is_whitespace())
+                {
+                    self.bump();
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn object(&mut self, pointer: &str) {
+        self.bump();
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('"') => {}
+                Some(',') => {
+                    self.bump();
+                    continue;
+                }
+                _ => {
+                    self.bump();
+                    return;
+                }
+            }
+            let key_position = self.position;
+            let key = self.string();
+            let member = format!("{pointer}/{}", escape_segment(&key));
+            self.map.keys.insert(member.clone(), key_position);
+            self.skip_whitespace();
+            if self.chars.peek() == Some(&':') {
+                self.bump();
+            }
+            self.value(member);
+        }
+    }
+
+    fn array(&mut self, pointer: &str) {
+        self.bump();
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some(']') | None => {
+                    self.bump();
+                    return;
+                }
+                Some(',') => {
+                    self.bump();
+                }
+                Some(_) => {
+                    self.value(format!("{pointer}/{index}"));
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    /// Consume a string literal and return its decoded contents.
+    fn string(&mut self) -> String {
+        let mut raw = String::new();
+        raw.extend(self.bump());
+        while let Some(c) = self.bump() {
+            raw.push(c);
+            match c {
+                '\\' => raw.extend(self.bump()),
+                '"' => break,
+                _ => {}
+            }
+        }
+        serde_json::from_str(&raw).unwrap_or(raw)
+    }
+}
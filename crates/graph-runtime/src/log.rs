@@ -0,0 +1,116 @@
+//! The `graph.log` host import.
+//!
+//! A node allowed the `log` capability may import `log(level, ptr, len)`
+//! from the `graph` module to emit a UTF-8 line from its `memory`. Lines are
+//! kept per node in its [`ExecutionRecord`] and, when the runtime echoes
+//! logs, printed to stderr as they arrive, prefixed with the node id.
+//!
+//! [`ExecutionRecord`]: crate::ExecutionRecord
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{format_err, Caller, Extern, Linker};
+
+use crate::sandbox::NodeState;
+
+pub(crate) const LOG_MODULE: &str = "graph";
+pub(crate) const LOG_IMPORT: &str = "log";
+
+/// Severity of a log line, numbered as passed to `graph.log`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Out-of-range levels are clamped rather than failing the node.
+    fn from_raw(level: i32) -> LogLevel {
+        match level {
+            i32::MIN..=0 => LogLevel::Trace,
+            1 => LogLevel::Debug,
+            2 => LogLevel::Info,
+            3 => LogLevel::Warn,
+            _ => LogLevel::Error,
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        })
+    }
+}
+
+/// A line a node logged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.level, self.message)
+    }
+}
+
+/// The lines a node has logged during one attempt.
+pub(crate) struct NodeLog {
+    node: String,
+    echo: bool,
+    pub(crate) lines: Vec<LogLine>,
+}
+
+impl NodeLog {
+    pub(crate) fn new(node: &str, echo: bool) -> Self {
+        Self {
+            node: node.to_string(),
+            echo,
+            lines: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, line: LogLine) {
+        if self.echo {
+            eprintln!("[{}] {line}", self.node);
+        }
+        self.lines.push(line);
+    }
+}
+
+/// Provide `graph.log` to nodes instantiated through `linker`.
+pub(crate) fn add_to_linker(linker: &mut Linker<NodeState>) -> wasmtime::Result<()> {
+    linker.func_wrap(
+        LOG_MODULE,
+        LOG_IMPORT,
+        |mut caller: Caller<'_, NodeState>, level: i32, ptr: u32, len: u32| {
+            let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+                return Err(format_err!("graph.log needs the node to export its memory"));
+            };
+            let start = ptr as usize;
+            let message = memory
+                .data(&caller)
+                .get(start..start + len as usize)
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+                .ok_or_else(|| format_err!("log message {ptr}+{len} is outside guest memory"))?;
+            caller.data_mut().log.push(LogLine {
+                level: LogLevel::from_raw(level),
+                message,
+            });
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
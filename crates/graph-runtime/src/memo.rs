@@ -0,0 +1,125 @@
+//! Memoized results of `pure` nodes.
+//!
+//! A pure node's outputs depend only on its module, its configuration and
+//! its inputs, so a successful execution is recorded under the hash of the
+//! module, the inputs it received, the node's port declarations and its
+//! `config`. A later execution with the same key is served from the cache
+//! instead of running the node. Results are kept in memory for the lifetime
+//! of a [`Runtime`] and, when the runtime has a disk cache, as JSON files
+//! under its `results` subdirectory.
+//!
+//! [`Runtime`]: crate::Runtime
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{content_hash, write_atomic};
+use crate::graph::NodeSpec;
+use crate::ports::PortValues;
+
+/// How pure nodes were served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoStats {
+    /// Executions served from the result cache.
+    pub hits: usize,
+    /// Results recorded for later runs.
+    pub stored: usize,
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.hits == 1 { "" } else { "s" };
+        write!(f, "{} hit{plural}, {} stored", self.hits, self.stored)
+    }
+}
+
+/// A recorded execution of a pure node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Memo {
+    /// Inputs as handed to the node, after any conversion.
+    pub(crate) inputs: PortValues,
+    pub(crate) outputs: PortValues,
+}
+
+#[derive(Default)]
+pub(crate) struct ResultCache {
+    enabled: bool,
+    disk: Option<PathBuf>,
+    memos: Mutex<HashMap<String, Memo>>,
+    hits: AtomicUsize,
+    stored: AtomicUsize,
+}
+
+impl ResultCache {
+    /// A cache kept in memory and, if `disk` is set, under `disk/results`.
+    pub(crate) fn new(disk: Option<PathBuf>) -> Self {
+        Self {
+            enabled: true,
+            disk: disk.map(|dir| dir.join("results")),
+            ..Self::default()
+        }
+    }
+
+    /// A cache that never serves or records anything.
+    pub(crate) fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn stats(&self) -> MemoStats {
+        MemoStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            stored: self.stored.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The key `node` is memoized under when it runs `wasm_hash` on
+    /// `inputs`, or `None` if the node is not pure or the cache is off.
+    pub(crate) fn key(
+        &self,
+        node: &NodeSpec,
+        wasm_hash: &str,
+        inputs: &PortValues,
+    ) -> Option<String> {
+        if !self.enabled || !node.pure {
+            return None;
+        }
+        let identity = (wasm_hash, inputs, &node.inputs, &node.outputs, &node.config);
+        let bytes = serde_json::to_vec(&identity).expect("memo keys serialize");
+        Some(content_hash(&bytes))
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Memo> {
+        let mut memos = self.memos.lock().expect("result cache poisoned");
+        let memo = match memos.get(key) {
+            Some(memo) => memo.clone(),
+            None => {
+                // Anything unreadable is a miss; the node simply runs again.
+                let path = self.disk.as_ref()?.join(format!("{key}.json"));
+                let memo: Memo = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+                memos.insert(key.to_string(), memo.clone());
+                memo
+            }
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(memo)
+    }
+
+    pub(crate) fn put(&self, key: String, memo: Memo) {
+        if let Some(dir) = &self.disk {
+            let json = serde_json::to_vec(&memo).expect("memos serialize");
+            // Like the module cache, this is only an optimisation.
+            let _ = write_atomic(&dir.join(format!("{key}.json")), &json);
+        }
+        self.memos
+            .lock()
+            .expect("result cache poisoned")
+            .insert(key, memo);
+        self.stored.fetch_add(1, Ordering::Relaxed);
+    }
+}
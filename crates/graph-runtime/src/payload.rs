@@ -0,0 +1,74 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A value flowing along a graph edge.
+///
+/// ABI v1 nodes consume and produce [`Payload::Int`]; ABI v2 nodes consume
+/// and produce [`Payload::Bytes`]. When a value crosses between the two, an
+/// integer is encoded as its decimal text (which is also valid JSON) and a
+/// byte payload is decoded the same way.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Payload {
+    Int(i32),
+    Bytes(Vec<u8>),
+}
+
+impl Payload {
+    /// Interpret the payload as an integer, parsing decimal text if needed.
+    pub fn to_int(&self) -> Option<i32> {
+        match self {
+            Payload::Int(value) => Some(*value),
+            Payload::Bytes(bytes) => std::str::from_utf8(bytes).ok()?.trim().parse().ok(),
+        }
+    }
+
+    /// Convert the payload into the byte form passed to ABI v2 nodes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Payload::Int(value) => value.to_string().into_bytes(),
+            Payload::Bytes(bytes) => bytes,
+        }
+    }
+
+    /// Borrow the payload as UTF-8 text, if it is a byte payload holding text.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Payload::Int(_) => None,
+            Payload::Bytes(bytes) => std::str::from_utf8(bytes).ok(),
+        }
+    }
+}
+
+impl From<i32> for Payload {
+    fn from(value: i32) -> Self {
+        Payload::Int(value)
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(bytes: Vec<u8>) -> Self {
+        Payload::Bytes(bytes)
+    }
+}
+
+impl From<&str> for Payload {
+    fn from(text: &str) -> Self {
+        Payload::Bytes(text.as_bytes().to_vec())
+    }
+}
+
+/// Integers print as-is, UTF-8 text prints quoted, and any other byte
+/// payload prints as its length.
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Payload::Int(value) => write!(f, "{value}"),
+            Payload::Bytes(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => write!(f, "{text:?}"),
+                Err(_) => write!(f, "<{} bytes>", bytes.len()),
+            },
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Named, typed node ports and the edges that connect them.
+//!
+//! A node either declares `inputs`/`outputs` explicitly or gets the implicit
+//! ports [`DEFAULT_INPUT`] (optional, untyped) and [`DEFAULT_OUTPUT`]
+//! (untyped). An input port is fed by an edge or by a graph input.
+//! Unconnected implicit inputs fall back to the legacy "last `dependsOn`
+//! entry wins" rule, so graphs without edges behave as before.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cache::content_hash;
+use crate::error::Error;
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::payload::Payload;
+
+/// Name of the implicit input port of a node without declared ports.
+pub const DEFAULT_INPUT: &str = "in";
+/// Name of the implicit output port of a node without declared ports.
+pub const DEFAULT_OUTPUT: &str = "out";
+
+/// Values keyed by port name, in declaration order.
+pub type PortValues = IndexMap<String, Payload>;
+
+/// The value type a port carries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortType {
+    /// Accepts any payload; no conversion is applied.
+    #[default]
+    Any,
+    I32,
+    /// UTF-8 text.
+    String,
+    /// A JSON document.
+    Json,
+    /// Opaque binary data.
+    Bytes,
+}
+
+impl PortType {
+    /// Whether a value produced by a `self` port may feed a `target` port.
+    pub fn feeds(self, target: PortType) -> bool {
+        self == target || self == PortType::Any || target == PortType::Any
+    }
+
+    /// Encode a payload as the JSON value placed in a port envelope.
+    pub fn encode(self, payload: &Payload) -> Option<Value> {
+        match (self, payload) {
+            (PortType::I32, payload) => payload.to_int().map(Value::from),
+            (PortType::String, Payload::Int(value)) => Some(Value::from(value.to_string())),
+            (PortType::String, Payload::Bytes(_)) => payload.as_str().map(Value::from),
+            (PortType::Json, Payload::Int(value)) => Some(Value::from(*value)),
+            (PortType::Json, Payload::Bytes(bytes)) => serde_json::from_slice(bytes).ok(),
+            (PortType::Bytes, payload) => Some(Value::from(payload.clone().into_bytes())),
+            (PortType::Any, Payload::Int(value)) => Some(Value::from(*value)),
+            (PortType::Any, Payload::Bytes(bytes)) => Some(match payload.as_str() {
+                Some(text) => Value::from(text),
+                None => Value::from(bytes.clone()),
+            }),
+        }
+    }
+
+    /// Decode a JSON value taken from a port envelope.
+    pub fn decode(self, value: Value) -> Option<Payload> {
+        let int = value.as_i64().and_then(|n| i32::try_from(n).ok());
+        match (self, value) {
+            (PortType::I32, _) => int.map(Payload::Int),
+            (PortType::Any, _) if int.is_some() => int.map(Payload::Int),
+            (PortType::String | PortType::Any, Value::String(text)) => {
+                Some(Payload::Bytes(text.into_bytes()))
+            }
+            (PortType::String, _) => None,
+            (PortType::Bytes, value) => serde_json::from_value(value).ok().map(Payload::Bytes),
+            (PortType::Json | PortType::Any, value) => {
+                serde_json::to_vec(&value).ok().map(Payload::Bytes)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PortType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PortType::Any => "any",
+            PortType::I32 => "i32",
+            PortType::String => "string",
+            PortType::Json => "json",
+            PortType::Bytes => "bytes",
+        })
+    }
+}
+
+/// A declared input or output port.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortSpec {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub ty: PortType,
+    /// Only meaningful for inputs: required ports must have an incoming edge.
+    #[serde(default = "default_required", skip_serializing_if = "is_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+fn is_required(required: &bool) -> bool {
+    *required
+}
+
+impl PortSpec {
+    fn implicit(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ty: PortType::Any,
+            required: false,
+        }
+    }
+}
+
+/// Which side of a node a port sits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortDirection {
+    Input,
+    Output,
+}
+
+impl fmt::Display for PortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PortDirection::Input => "input",
+            PortDirection::Output => "output",
+        })
+    }
+}
+
+/// A `node.port` reference, as used in edges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PortRef {
+    pub node: String,
+    pub port: String,
+}
+
+impl FromStr for PortRef {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.rsplit_once('.') {
+            Some((node, port)) if !node.is_empty() && !port.is_empty() => Ok(PortRef {
+                node: node.to_string(),
+                port: port.to_string(),
+            }),
+            _ => Err(format!(
+                "invalid port reference {value:?}; expected \"node.port\""
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for PortRef {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PortRef> for String {
+    fn from(port: PortRef) -> Self {
+        port.to_string()
+    }
+}
+
+impl fmt::Display for PortRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.node, self.port)
+    }
+}
+
+/// A connection from one node's output port to another node's input port.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeSpec {
+    pub from: PortRef,
+    pub to: PortRef,
+}
+
+impl NodeSpec {
+    /// Whether the node declares explicit ports rather than the implicit
+    /// [`DEFAULT_INPUT`]/[`DEFAULT_OUTPUT`] pair.
+    pub fn has_ports(&self) -> bool {
+        !self.inputs.is_empty() || !self.outputs.is_empty()
+    }
+
+    /// The node's input ports, declared or implicit.
+    pub fn input_ports(&self) -> Vec<PortSpec> {
+        if self.has_ports() {
+            self.inputs.clone()
+        } else {
+            vec![PortSpec::implicit(DEFAULT_INPUT)]
+        }
+    }
+
+    /// The node's output ports, declared or implicit.
+    pub fn output_ports(&self) -> Vec<PortSpec> {
+        if self.has_ports() {
+            self.outputs.clone()
+        } else {
+            vec![PortSpec::implicit(DEFAULT_OUTPUT)]
+        }
+    }
+
+    pub(crate) fn port(&self, direction: PortDirection, name: &str) -> Option<PortSpec> {
+        let ports = match direction {
+            PortDirection::Input => self.input_ports(),
+            PortDirection::Output => self.output_ports(),
+        };
+        ports.into_iter().find(|port| port.name == name)
+    }
+}
+
+impl GraphSpec {
+    /// Edges whose target is `node`.
+    pub fn incoming_edges<'a>(&'a self, node: &'a str) -> impl Iterator<Item = &'a EdgeSpec> + 'a {
+        self.edges.iter().filter(move |edge| edge.to.node == node)
+    }
+
+    /// Upstream node ids of `node`: its `dependsOn` entries followed by the
+    /// sources of its incoming edges, without duplicates.
+    pub fn dependencies(&self, node: &NodeSpec) -> Vec<String> {
+        let mut dependencies = node.depends_on.clone();
+        for edge in self.incoming_edges(&node.id) {
+            if !dependencies.contains(&edge.from.node) {
+                dependencies.push(edge.from.node.clone());
+            }
+        }
+        dependencies
+    }
+
+    /// Hex SHA-256 of `node`'s port declarations and of the output port or
+    /// graph input feeding each of its input ports, so that rewiring a node
+    /// is noticed even when the values it receives look the same.
+    pub(crate) fn wiring_hash(&self, node: &NodeSpec) -> String {
+        let mut sources: Vec<(&str, String)> = self
+            .incoming_edges(&node.id)
+            .map(|edge| (edge.to.port.as_str(), edge.from.to_string()))
+            .collect();
+        for input in &self.inputs {
+            for to in input.to.iter().filter(|to| to.node == node.id) {
+                sources.push((to.port.as_str(), format!("inputs.{}", input.name)));
+            }
+        }
+        sources.sort();
+        let identity = (&node.inputs, &node.outputs, sources);
+        content_hash(&serde_json::to_vec(&identity).expect("wiring serializes"))
+    }
+}
+
+/// Where in the graph document a port problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PortSite {
+    /// The `from` or `to` end of the edge at this index.
+    Edge(usize, &'static str),
+    /// The input port declaration `(node index, port index)`.
+    Input(usize, usize),
+    /// The `to` entry `(input index, target index)` of a graph input.
+    GraphInput(usize, usize),
+    /// The `from` of the graph output at this index.
+    GraphOutput(usize),
+    /// The graph's report template.
+    Report,
+}
+
+/// Check that every edge and graph input connects existing, type-compatible
+/// ports, that no input port is fed more than once, that every graph output
+/// and report placeholder names an existing output port and that every
+/// required input port is connected. Every problem found is returned.
+pub(crate) fn check_ports(graph: &GraphSpec) -> Vec<(PortSite, Error)> {
+    let mut by_id = HashMap::with_capacity(graph.nodes.len());
+    for node in &graph.nodes {
+        by_id.entry(node.id.as_str()).or_insert(node);
+    }
+
+    let port = |port: &PortRef, direction| {
+        let node = by_id
+            .get(port.node.as_str())
+            .ok_or_else(|| Error::UnknownEdgeNode { port: port.clone() })?;
+        node.port(direction, &port.port)
+            .ok_or_else(|| Error::UnknownPort {
+                port: port.clone(),
+                direction,
+            })
+    };
+
+    let mut problems = Vec::new();
+    let mut connected: HashSet<&PortRef> = HashSet::new();
+    for (index, edge) in graph.edges.iter().enumerate() {
+        match (
+            port(&edge.from, PortDirection::Output),
+            port(&edge.to, PortDirection::Input),
+        ) {
+            (Ok(from), Ok(to)) if !from.ty.feeds(to.ty) => problems.push((
+                PortSite::Edge(index, "to"),
+                Error::PortTypeMismatch {
+                    from: edge.from.clone(),
+                    from_type: from.ty,
+                    to: edge.to.clone(),
+                    to_type: to.ty,
+                },
+            )),
+            (from, to) => {
+                for (end, result) in [("from", from), ("to", to)] {
+                    if let Err(err) = result {
+                        problems.push((PortSite::Edge(index, end), err));
+                    }
+                }
+            }
+        }
+        if !connected.insert(&edge.to) {
+            problems.push((
+                PortSite::Edge(index, "to"),
+                Error::DuplicateEdge(edge.to.clone()),
+            ));
+        }
+    }
+
+    for (input_index, input) in graph.inputs.iter().enumerate() {
+        for (target_index, to) in input.to.iter().enumerate() {
+            let site = PortSite::GraphInput(input_index, target_index);
+            match port(to, PortDirection::Input) {
+                Ok(target) if !input.ty.feeds(target.ty) => problems.push((
+                    site,
+                    Error::GraphInputTypeMismatch {
+                        input: input.name.clone(),
+                        ty: input.ty,
+                        to: to.clone(),
+                        to_type: target.ty,
+                    },
+                )),
+                Ok(_) => {}
+                Err(_) => problems.push((
+                    site,
+                    Error::UnknownGraphPort {
+                        name: input.name.clone(),
+                        port: to.clone(),
+                        direction: PortDirection::Input,
+                    },
+                )),
+            }
+            if !connected.insert(to) {
+                problems.push((site, Error::DuplicateEdge(to.clone())));
+            }
+        }
+    }
+
+    for (index, output) in graph.outputs.iter().enumerate() {
+        if port(&output.from, PortDirection::Output).is_err() {
+            problems.push((
+                PortSite::GraphOutput(index),
+                Error::UnknownGraphPort {
+                    name: output.name.clone(),
+                    port: output.from.clone(),
+                    direction: PortDirection::Output,
+                },
+            ));
+        }
+    }
+
+    for from in graph.report.iter().flat_map(|report| report.ports()) {
+        if port(from, PortDirection::Output).is_err() {
+            problems.push((PortSite::Report, Error::UnknownReportPort(from.clone())));
+        }
+    }
+
+    for (node_index, node) in graph.nodes.iter().enumerate() {
+        for (port_index, input) in node.inputs.iter().enumerate() {
+            let target = PortRef {
+                node: node.id.clone(),
+                port: input.name.clone(),
+            };
+            if input.required && !connected.contains(&target) {
+                problems.push((
+                    PortSite::Input(node_index, port_index),
+                    Error::UnconnectedPort(target),
+                ));
+            }
+        }
+    }
+
+    problems
+}
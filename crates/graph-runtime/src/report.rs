@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use crate::executor::{ExecutionRecord, ExecutionState};
+use crate::failure::Outcome;
+use crate::graph::GraphSpec;
+use crate::ports::{PortValues, DEFAULT_INPUT, DEFAULT_OUTPUT};
+
+/// Render the final state as a compact report.
+pub fn format_state(state: &ExecutionState) -> String {
+    let mut lines = Vec::with_capacity(state.len() + 1);
+    for (id, record) in state {
+        let deps = if record.dependencies.is_empty() {
+            "none".to_string()
+        } else {
+            record.dependencies.join(", ")
+        };
+        let result = match (record.outcome, &record.error) {
+            (Outcome::Skipped, Some(error)) => format!("skipped after {}", error.kind),
+            (Outcome::Skipped, None) => "skipped".to_string(),
+            (Outcome::Defaulted, Some(error)) => {
+                format!("{} [default after {}]", format_ports(record), error.kind)
+            }
+            _ if record.cached => format!("{} [cached]", format_ports(record)),
+            _ if record.reused => format!("{} [reused]", format_ports(record)),
+            _ if record.attempts > 1 => {
+                format!("{} [attempt {}]", format_ports(record), record.attempts)
+            }
+            _ => format_ports(record),
+        };
+        lines.push(format!("{id}: {result} (deps: {deps})"));
+    }
+    lines.join("\n")
+}
+
+/// Render the graph's `report` template against `state`, if it has one.
+pub fn format_report(graph: &GraphSpec, state: &ExecutionState) -> Option<String> {
+    graph.report.as_ref().map(|report| report.render(state))
+}
+
+/// Nodes using the implicit ports keep the original `input=.. -> output=..`
+/// form; nodes with declared ports list every port by name.
+fn format_ports(record: &ExecutionRecord) -> String {
+    let implicit = record.inputs.keys().all(|name| name == DEFAULT_INPUT)
+        && record.outputs.keys().eq([DEFAULT_OUTPUT]);
+    match (implicit, record.input(), record.output()) {
+        (true, Some(input), Some(output)) => format!("input={input} -> output={output}"),
+        _ => format!(
+            "inputs {} -> outputs {}",
+            format_values(&record.inputs),
+            format_values(&record.outputs)
+        ),
+    }
+}
+
+pub(crate) fn format_values(values: &PortValues) -> String {
+    let values: Vec<String> = values
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    format!("{{{}}}", values.join(", "))
+}
+
+/// Render when each node ran and on which worker, followed by the achieved
+/// parallelism: total node time divided by the wall-clock span of the run.
+pub fn format_timings(state: &ExecutionState) -> String {
+    let width = state.keys().map(String::len).max().unwrap_or(0);
+    let mut lines = Vec::with_capacity(state.len() + 1);
+    for (id, record) in state {
+        let timing = &record.timing;
+        lines.push(format!(
+            "{id:<width$}  worker {}  {:>9} -> {:>9}  ({})",
+            timing.worker,
+            format_duration(timing.started),
+            format_duration(timing.finished),
+            format_duration(timing.duration()),
+        ));
+    }
+    let busy: Duration = state.values().map(|record| record.timing.duration()).sum();
+    let span = state
+        .values()
+        .map(|record| record.timing.finished)
+        .max()
+        .unwrap_or_default()
+        .saturating_sub(
+            state
+                .values()
+                .map(|record| record.timing.started)
+                .min()
+                .unwrap_or_default(),
+        );
+    if !span.is_zero() {
+        lines.push(format!(
+            "Parallelism: {:.2}x ({} of node time in {} wall-clock)",
+            busy.as_secs_f64() / span.as_secs_f64(),
+            format_duration(busy),
+            format_duration(span),
+        ));
+    }
+    lines.join("\n")
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Pretty-print the final state as a compact report, followed by the
+/// graph's `report` line.
+pub fn log_state(graph: &GraphSpec, state: &ExecutionState) {
+    println!("{}", format_state(state));
+    if let Some(report) = format_report(graph, state) {
+        println!("{report}");
+    }
+}
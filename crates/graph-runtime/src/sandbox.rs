@@ -0,0 +1,278 @@
+//! Per-node sandbox policy.
+//!
+//! A policy caps the resources a node may use (linear memory, table size
+//! and wasm stack) and lists the host capabilities it may link against.
+//! Policies are declared as `sandbox` on a node or on the whole graph; node
+//! values win field by field. Without a policy a node gets no imports at
+//! all, which is what every node has always been instantiated with.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{Module, ResourceLimiter};
+use wasmtime_wasi::p1::WasiP1Ctx;
+use wasmtime_wasi::{FsPerms, WasiCtxBuilder};
+
+use crate::error::{Error, Result};
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::log::{NodeLog, LOG_IMPORT, LOG_MODULE};
+
+/// Size of a wasm linear memory page.
+pub const WASM_PAGE_SIZE: u64 = 65536;
+
+/// Largest accepted `maxStackBytes`; worker threads reserve enough native
+/// stack for a wasm stack of this size.
+pub const MAX_STACK_BYTES: u64 = 1 << 20;
+
+/// Host functionality a node may import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    /// The runtime's `graph.log` import.
+    Log,
+    /// WASI clocks.
+    Clock,
+    /// WASI `random_get`.
+    Random,
+    /// WASI file descriptors and paths, confined to the policy's preopens.
+    Fs,
+}
+
+impl Capability {
+    /// The capability an import needs, `Ok(None)` for imports every node may
+    /// use, or `Err(())` when the runtime does not provide the import.
+    pub(crate) fn required_by(module: &str, name: &str) -> Result<Option<Capability>, ()> {
+        match (module, name) {
+            (LOG_MODULE, LOG_IMPORT) => Ok(Some(Capability::Log)),
+            (WASI, "clock_time_get" | "clock_res_get") => Ok(Some(Capability::Clock)),
+            (WASI, "random_get") => Ok(Some(Capability::Random)),
+            (WASI, name)
+                if name.starts_with("fd_")
+                    || name.starts_with("path_")
+                    || name == "poll_oneoff" =>
+            {
+                Ok(Some(Capability::Fs))
+            }
+            // Nodes see no arguments and an empty environment, and may
+            // always exit or yield.
+            (
+                WASI,
+                "args_get" | "args_sizes_get" | "environ_get" | "environ_sizes_get" | "proc_exit"
+                | "sched_yield",
+            ) => Ok(None),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Capability {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "log" => Ok(Capability::Log),
+            "clock" => Ok(Capability::Clock),
+            "random" => Ok(Capability::Random),
+            "fs" => Ok(Capability::Fs),
+            _ => Err(format!(
+                "unknown capability {value:?}; expected log, clock, random or fs"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::Log => "log",
+            Capability::Clock => "clock",
+            Capability::Random => "random",
+            Capability::Fs => "fs",
+        })
+    }
+}
+
+const WASI: &str = "wasi_snapshot_preview1";
+
+/// A host directory made visible to a node with the `fs` capability.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preopen {
+    /// Host directory, relative to the graph file.
+    pub host: String,
+    /// Path the node opens it under.
+    pub guest: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub writable: bool,
+}
+
+/// Resource caps and capability whitelist. Unset fields fall back to the
+/// graph's policy, then to no cap and no capabilities.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sandbox {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_pages: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_table_elements: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_stack_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<Capability>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preopens: Option<Vec<Preopen>>,
+}
+
+impl Sandbox {
+    /// Fill unset fields from `fallback`.
+    pub fn or(self, fallback: &Sandbox) -> Sandbox {
+        Sandbox {
+            max_memory_pages: self.max_memory_pages.or(fallback.max_memory_pages),
+            max_table_elements: self.max_table_elements.or(fallback.max_table_elements),
+            max_stack_bytes: self.max_stack_bytes.or(fallback.max_stack_bytes),
+            allow: self.allow.or_else(|| fallback.allow.clone()),
+            preopens: self.preopens.or_else(|| fallback.preopens.clone()),
+        }
+    }
+
+    /// The policy that applies to `node`.
+    pub fn for_node(graph: &GraphSpec, node: &NodeSpec) -> Sandbox {
+        let graph_policy = graph.sandbox.clone().unwrap_or_default();
+        node.sandbox.clone().unwrap_or_default().or(&graph_policy)
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.allow
+            .as_ref()
+            .is_some_and(|allow| allow.contains(&capability))
+    }
+
+    /// Check every import of `module` against the policy, returning which
+    /// host modules the node needs linked in.
+    pub(crate) fn check_imports(&self, node: &str, module: &Module) -> Result<Imports> {
+        let mut imports = Imports::default();
+        for import in module.imports() {
+            let name = format!("{}.{}", import.module(), import.name());
+            match Capability::required_by(import.module(), import.name()) {
+                Ok(Some(capability)) if !self.allows(capability) => {
+                    return Err(Error::ImportDenied {
+                        node: node.to_string(),
+                        import: name,
+                        capability,
+                    })
+                }
+                Ok(_) => {
+                    imports.wasi |= import.module() == WASI;
+                    imports.log |= import.module() == LOG_MODULE;
+                }
+                Err(()) => {
+                    return Err(Error::UnknownImport {
+                        node: node.to_string(),
+                        import: name,
+                    })
+                }
+            }
+        }
+        Ok(imports)
+    }
+
+    /// A WASI context exposing only the preopens, and only with `fs`.
+    pub(crate) fn wasi_ctx(&self, base_dir: &Path) -> Result<WasiP1Ctx> {
+        let mut builder = WasiCtxBuilder::new();
+        if self.allows(Capability::Fs) {
+            for preopen in self.preopens.iter().flatten() {
+                let host = base_dir.join(&preopen.host);
+                let perms = if preopen.writable {
+                    FsPerms::ReadWrite
+                } else {
+                    FsPerms::ReadOnly
+                };
+                builder
+                    .preopened_dir(&host, &preopen.guest, perms)
+                    .map_err(|err| Error::Preopen {
+                        path: host.clone(),
+                        reason: format!("{err:#}"),
+                    })?;
+            }
+        }
+        Ok(builder.build_p1())
+    }
+
+    pub(crate) fn limiter(&self) -> NodeLimiter {
+        NodeLimiter {
+            max_memory_pages: self.max_memory_pages,
+            max_table_elements: self.max_table_elements,
+            exceeded: None,
+        }
+    }
+}
+
+/// The host modules a node imports from.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Imports {
+    pub(crate) wasi: bool,
+    pub(crate) log: bool,
+}
+
+/// Host state of a node's store.
+pub(crate) struct NodeState {
+    pub(crate) limiter: NodeLimiter,
+    /// Present when the node imports WASI.
+    pub(crate) wasi: Option<WasiP1Ctx>,
+    pub(crate) log: NodeLog,
+}
+
+/// Enforces a policy's memory and table caps, remembering the first
+/// request it refused so the failure can be reported clearly.
+pub(crate) struct NodeLimiter {
+    max_memory_pages: Option<u64>,
+    max_table_elements: Option<u64>,
+    pub(crate) exceeded: Option<Exceeded>,
+}
+
+/// A memory or table growth the policy refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exceeded {
+    /// `"memory pages"` or `"table elements"`.
+    pub resource: &'static str,
+    pub requested: u64,
+    pub limit: u64,
+}
+
+impl NodeLimiter {
+    fn allow(&mut self, resource: &'static str, requested: u64, limit: Option<u64>) -> bool {
+        match limit {
+            Some(limit) if requested > limit => {
+                self.exceeded.get_or_insert(Exceeded {
+                    resource,
+                    requested,
+                    limit,
+                });
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+impl ResourceLimiter for NodeLimiter {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let pages = (desired as u64).div_ceil(WASM_PAGE_SIZE);
+        Ok(self.allow("memory pages", pages, self.max_memory_pages))
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(self.allow("table elements", desired as u64, self.max_table_elements))
+    }
+}
@@ -0,0 +1,214 @@
+//! Concurrent execution of independent graph branches.
+//!
+//! The calling thread owns the execution state: it resolves a node's inputs
+//! once every dependency has finished and hands the node to a bounded pool
+//! of worker threads. Ready nodes are dispatched in topological order and
+//! the final state is reassembled in that order, so results do not depend on
+//! which node happened to finish first. A panic on a worker stops the
+//! dispatching and is re-raised on the calling thread once the other
+//! workers have finished.
+
+use std::collections::{BTreeSet, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::executor::{resolve_inputs, upstream_skipped, Call, ExecutionState, Runtime};
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::ports::PortValues;
+use crate::trace::NodeEvent;
+
+/// Native stack of a worker thread. Wasm runs on the worker's own stack, so
+/// this leaves ample room above the largest `maxStackBytes` a sandbox allows.
+const WORKER_STACK_BYTES: usize = 8 << 20;
+
+/// When a node ran, relative to the start of the graph run, and on which
+/// worker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    pub worker: usize,
+    pub started: Duration,
+    pub finished: Duration,
+}
+
+impl Timing {
+    pub fn duration(&self) -> Duration {
+        self.finished.saturating_sub(self.started)
+    }
+}
+
+struct Job<'a> {
+    index: usize,
+    /// `None` when an upstream node was skipped.
+    inputs: Option<PortValues>,
+    /// The node's reusable execution from the previous run.
+    previous: Option<&'a NodeEvent>,
+}
+
+struct Done {
+    index: usize,
+    timing: Timing,
+    /// `Err` with the payload of a panic while running the node.
+    result: thread::Result<Result<Call>>,
+}
+
+/// Execute `ordered` (a topological order of `graph`) on up to
+/// `runtime.jobs()` worker threads, with `graph_inputs` bound to the
+/// graph's inputs.
+///
+/// Nodes downstream of a skipped node are skipped without running. When a
+/// node fails and its `onError` policy does not handle it, no further nodes
+/// are started; nodes already running are allowed to finish and the failure
+/// of the earliest node in topological order is returned alongside the
+/// records of every node that finished.
+pub(crate) fn run(
+    runtime: &Runtime,
+    base_dir: &Path,
+    graph: &GraphSpec,
+    ordered: &[&NodeSpec],
+    graph_inputs: &PortValues,
+) -> (ExecutionState, Option<Error>) {
+    let epoch = Instant::now();
+    let position: HashMap<&str, usize> = ordered
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.id.as_str(), index))
+        .collect();
+    let mut pending = vec![0usize; ordered.len()];
+    let mut dependents = vec![Vec::new(); ordered.len()];
+    for (index, node) in ordered.iter().enumerate() {
+        for dep in graph.dependencies(node) {
+            pending[index] += 1;
+            dependents[position[dep.as_str()]].push(index);
+        }
+    }
+    let mut ready: BTreeSet<usize> = (0..ordered.len()).filter(|&i| pending[i] == 0).collect();
+
+    let (job_tx, job_rx) = mpsc::channel::<Job<'_>>();
+    let (done_tx, done_rx) = mpsc::channel::<Done>();
+    let job_rx = Mutex::new(job_rx);
+    let workers = runtime.jobs().min(ordered.len()).max(1);
+
+    let mut state = ExecutionState::with_capacity(ordered.len());
+    let mut failures: Vec<(usize, Error)> = Vec::new();
+    let mut panicked = None;
+
+    thread::scope(|scope| {
+        for worker in 0..workers {
+            let job_rx = &job_rx;
+            let done_tx = done_tx.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("graph-runtime-worker-{worker}"))
+                .stack_size(WORKER_STACK_BYTES)
+                .spawn_scoped(scope, move || loop {
+                    // Hold the lock only while waiting for the next job.
+                    let job = job_rx.lock().expect("job queue poisoned").recv();
+                    let Ok(Job {
+                        index,
+                        inputs,
+                        previous,
+                    }) = job
+                    else {
+                        return;
+                    };
+                    let started = epoch.elapsed();
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        runtime.call_node(base_dir, graph, ordered[index], inputs, previous)
+                    }));
+                    let timing = Timing {
+                        worker,
+                        started,
+                        finished: epoch.elapsed(),
+                    };
+                    if done_tx
+                        .send(Done {
+                            index,
+                            timing,
+                            result,
+                        })
+                        .is_err()
+                    {
+                        return;
+                    }
+                });
+            spawned.expect("failed to spawn a worker thread");
+        }
+        // Only the workers may keep the channel open, so that it disconnects
+        // if they all die.
+        drop(done_tx);
+
+        let mut in_flight = 0;
+        loop {
+            while in_flight < workers && failures.is_empty() && panicked.is_none() {
+                let Some(index) = ready.pop_first() else {
+                    break;
+                };
+                let node = ordered[index];
+                let inputs = if upstream_skipped(graph, node, &state) {
+                    Ok(None)
+                } else {
+                    resolve_inputs(graph, node, &state, graph_inputs).map(Some)
+                };
+                let previous = runtime.previous_run(graph, node, &state, graph_inputs);
+                match inputs {
+                    Ok(inputs) => {
+                        job_tx
+                            .send(Job {
+                                index,
+                                inputs,
+                                previous,
+                            })
+                            .expect("workers outlive the dispatcher");
+                        in_flight += 1;
+                    }
+                    Err(err) => failures.push((index, err)),
+                }
+            }
+            if in_flight == 0 {
+                break;
+            }
+
+            let Ok(done) = done_rx.recv() else {
+                // Every worker is gone without reporting back, so one of
+                // them panicked; leaving the scope re-raises the panic.
+                break;
+            };
+            in_flight -= 1;
+            match done.result {
+                Ok(Ok(call)) => {
+                    let node = ordered[done.index];
+                    state.insert(node.id.clone(), call.record(graph, node, done.timing));
+                    for &dependent in &dependents[done.index] {
+                        pending[dependent] -= 1;
+                        if pending[dependent] == 0 {
+                            ready.insert(dependent);
+                        }
+                    }
+                }
+                Ok(Err(err)) => failures.push((done.index, err)),
+                Err(payload) => panicked = Some(payload),
+            }
+        }
+        // Closing the queue lets the idle workers exit.
+        drop(job_tx);
+    });
+    if let Some(payload) = panicked {
+        panic::resume_unwind(payload);
+    }
+
+    let failure = failures
+        .into_iter()
+        .min_by_key(|(index, _)| *index)
+        .map(|(_, err)| err);
+    let state = ordered
+        .iter()
+        .filter_map(|node| state.swap_remove_entry(&node.id))
+        .collect();
+    (state, failure)
+}
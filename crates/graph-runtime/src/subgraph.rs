@@ -0,0 +1,229 @@
+//! Subgraph nodes: another graph file embedded as a single node.
+//!
+//! A node with a `graph` path instead of a `wasm` module stands for the
+//! graph in that file. Its input ports are the embedded graph's `inputs`
+//! (required unless they have a `default`) and its output ports are its
+//! `outputs`, so the node is wired up like any other:
+//!
+//! ```json
+//! { "id": "profile", "graph": "./profile.graph.json" }
+//! ```
+//!
+//! Besides `id` and `graph`, a subgraph node may only set `dependsOn` and
+//! `limits`. Keys that describe a single module — ports, `sandbox`,
+//! `onError`, `pure` and `config` — belong on the nodes of the embedded
+//! graph, and validation rejects them here.
+//!
+//! Loading a graph flattens subgraph nodes away. The embedded nodes join the
+//! graph with their ids namespaced by the subgraph node's id
+//! (`profile/calcDiscount`), their paths rebased onto the including graph and
+//! the subgraph node's `limits`, then the embedded graph's `limits` and
+//! `sandbox`, folded into their own. Each of them depends on whatever the
+//! subgraph node depends on. Edges and graph inputs that fed a subgraph input
+//! port feed the ports it was wired to instead, and edges, graph outputs and
+//! report placeholders that read a subgraph output port read the node output
+//! behind it. A subgraph input nothing feeds keeps its `default` as a graph
+//! input named after the port, e.g. `profile/userId`. Nodes that depend on a
+//! subgraph node depend on every node inside it.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::interface::GraphInput;
+use crate::limits::Limits;
+use crate::ports::{EdgeSpec, PortDirection, PortRef, PortSpec};
+use crate::sandbox::{Preopen, Sandbox};
+
+/// The input and output ports of a node embedding `child`.
+pub(crate) fn ports(child: &GraphSpec) -> (Vec<PortSpec>, Vec<PortSpec>) {
+    let inputs = child
+        .inputs
+        .iter()
+        .map(|input| PortSpec {
+            name: input.name.clone(),
+            ty: input.ty,
+            required: input.default.is_none(),
+        })
+        .collect();
+    let outputs = child
+        .outputs
+        .iter()
+        .map(|output| {
+            let ty = child
+                .nodes
+                .iter()
+                .find(|node| node.id == output.from.node)
+                .and_then(|node| node.port(PortDirection::Output, &output.from.port))
+                .map(|port| port.ty)
+                .unwrap_or_default();
+            PortSpec {
+                name: output.name.clone(),
+                ty,
+                required: true,
+            }
+        })
+        .collect();
+    (inputs, outputs)
+}
+
+/// Replace every subgraph node of `graph` with the nodes of its already
+/// flattened graph in `subgraphs`, keyed by node id.
+pub(crate) fn flatten(graph: GraphSpec, subgraphs: &HashMap<String, GraphSpec>) -> GraphSpec {
+    if subgraphs.is_empty() {
+        return graph;
+    }
+    let fed = |port: &PortRef| {
+        graph.edges.iter().any(|edge| &edge.to == port)
+            || graph.inputs.iter().any(|input| input.to.contains(port))
+    };
+
+    let mut nodes = Vec::new();
+    let mut inner_edges = Vec::new();
+    let mut defaults = Vec::new();
+    let mut targets: HashMap<PortRef, Vec<PortRef>> = HashMap::new();
+    let mut sources: HashMap<PortRef, PortRef> = HashMap::new();
+    let mut members: HashMap<&str, Vec<String>> = HashMap::new();
+    for node in &graph.nodes {
+        let Some(child) = subgraphs.get(&node.id) else {
+            nodes.push(node.clone());
+            continue;
+        };
+        let dir = Path::new(node.graph.as_deref().unwrap_or_default())
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let id = |inner: &str| format!("{}/{inner}", node.id);
+        let port = |inner: &PortRef| PortRef {
+            node: id(&inner.node),
+            port: inner.port.clone(),
+        };
+
+        for inner in &child.nodes {
+            nodes.push(embed(inner, child, dir, node));
+        }
+        members.insert(
+            &node.id,
+            child.nodes.iter().map(|inner| id(&inner.id)).collect(),
+        );
+        inner_edges.extend(child.edges.iter().map(|edge| EdgeSpec {
+            from: port(&edge.from),
+            to: port(&edge.to),
+        }));
+        for input in &child.inputs {
+            let to: Vec<PortRef> = input.to.iter().map(port).collect();
+            let outer = PortRef {
+                node: node.id.clone(),
+                port: input.name.clone(),
+            };
+            if !fed(&outer) && input.default.is_some() {
+                defaults.push(GraphInput {
+                    name: id(&input.name),
+                    to: to.clone(),
+                    ..input.clone()
+                });
+            }
+            targets.insert(outer, to);
+        }
+        for output in &child.outputs {
+            let outer = PortRef {
+                node: node.id.clone(),
+                port: output.name.clone(),
+            };
+            sources.insert(outer, port(&output.from));
+        }
+    }
+
+    let source = |port: &PortRef| sources.get(port).unwrap_or(port).clone();
+    let target = |port: &PortRef| {
+        targets
+            .get(port)
+            .cloned()
+            .unwrap_or_else(|| vec![port.clone()])
+    };
+    for node in &mut nodes {
+        node.depends_on = node
+            .depends_on
+            .iter()
+            .flat_map(|dep| {
+                members
+                    .get(dep.as_str())
+                    .cloned()
+                    .unwrap_or_else(|| vec![dep.clone()])
+            })
+            .collect();
+    }
+    let mut edges: Vec<EdgeSpec> = graph
+        .edges
+        .iter()
+        .flat_map(|edge| {
+            let from = source(&edge.from);
+            target(&edge.to).into_iter().map(move |to| EdgeSpec {
+                from: from.clone(),
+                to,
+            })
+        })
+        .collect();
+    edges.extend(inner_edges);
+    let mut inputs: Vec<GraphInput> = graph
+        .inputs
+        .iter()
+        .map(|input| GraphInput {
+            to: input.to.iter().flat_map(target).collect(),
+            ..input.clone()
+        })
+        .collect();
+    inputs.extend(defaults);
+    let mut outputs = graph.outputs.clone();
+    for output in &mut outputs {
+        output.from = source(&output.from);
+    }
+    let report = graph.report.clone().map(|report| report.map_ports(source));
+
+    GraphSpec {
+        nodes,
+        edges,
+        inputs,
+        outputs,
+        report,
+        ..graph
+    }
+}
+
+/// `inner`, a node of `child`, as a node of the including graph, embedded
+/// by the subgraph node `outer` from `dir`.
+fn embed(inner: &NodeSpec, child: &GraphSpec, dir: &Path, outer: &NodeSpec) -> NodeSpec {
+    let id = |inner: &str| format!("{}/{inner}", outer.id);
+    let rebase = |path: &str| dir.join(path).to_string_lossy().into_owned();
+    let limits = [inner.limits, outer.limits, child.limits]
+        .into_iter()
+        .flatten()
+        .reduce(Limits::or);
+    let sandbox = match (&inner.sandbox, &child.sandbox) {
+        (Some(sandbox), Some(fallback)) => Some(sandbox.clone().or(fallback)),
+        (sandbox, fallback) => sandbox.clone().or_else(|| fallback.clone()),
+    };
+    NodeSpec {
+        id: id(&inner.id),
+        wasm: rebase(&inner.wasm),
+        depends_on: inner
+            .depends_on
+            .iter()
+            .map(|dep| id(dep))
+            .chain(outer.depends_on.iter().cloned())
+            .collect(),
+        limits,
+        sandbox: sandbox.map(|sandbox| Sandbox {
+            preopens: sandbox.preopens.map(|preopens| {
+                preopens
+                    .into_iter()
+                    .map(|preopen| Preopen {
+                        host: rebase(&preopen.host),
+                        ..preopen
+                    })
+                    .collect()
+            }),
+            ..sandbox
+        }),
+        ..inner.clone()
+    }
+}
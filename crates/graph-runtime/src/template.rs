@@ -0,0 +1,159 @@
+//! Report templates: the `report` line of `graph.json`.
+//!
+//! A template is text with `{{node.port}}` placeholders, each replaced by
+//! the value the node produced on that output port when the run is
+//! reported:
+//!
+//! ```json
+//! { "report": "User {{fetchUser.userId}} receives discount score {{renderProfile.score}}." }
+//! ```
+//!
+//! Whitespace inside the braces is ignored. Text values are inserted as is,
+//! integers in decimal and other bytes as `<N bytes>`; ports of nodes that
+//! did not produce a value render as `???`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::executor::ExecutionState;
+use crate::ports::PortRef;
+
+/// A parsed report template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ReportTemplate {
+    text: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Port(PortRef),
+}
+
+impl ReportTemplate {
+    /// The output ports the template refers to, in order of appearance.
+    pub fn ports(&self) -> impl Iterator<Item = &PortRef> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Port(port) => Some(port),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// The template with every placeholder's port replaced by `map`.
+    pub(crate) fn map_ports(self, map: impl Fn(&PortRef) -> PortRef) -> Self {
+        let parts: Vec<Part> = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => Part::Text(text.clone()),
+                Part::Port(port) => Part::Port(map(port)),
+            })
+            .collect();
+        let text = parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Port(port) => format!("{{{{{port}}}}}"),
+            })
+            .collect();
+        ReportTemplate { text, parts }
+    }
+
+    /// Fill in the placeholders with the outputs recorded in `state`.
+    pub fn render(&self, state: &ExecutionState) -> String {
+        let mut rendered = String::with_capacity(self.text.len());
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => rendered.push_str(text),
+                Part::Port(port) => {
+                    match state
+                        .get(&port.node)
+                        .and_then(|record| record.outputs.get(&port.port))
+                    {
+                        Some(value) => match value.as_str() {
+                            Some(text) => rendered.push_str(text),
+                            None => rendered.push_str(&value.to_string()),
+                        },
+                        None => rendered.push_str("???"),
+                    }
+                }
+            }
+        }
+        rendered
+    }
+}
+
+impl FromStr for ReportTemplate {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let offset = text.len() - rest.len();
+            let open = rest.find("{{");
+            let close = rest.find("}}");
+            if let Some(close) = close.filter(|&close| open.is_none_or(|open| close < open)) {
+                return Err(format!(
+                    "unmatched `}}}}` at character {}",
+                    char_offset(text, offset + close)
+                ));
+            }
+            let Some(open) = open else {
+                parts.push(Part::Text(rest.to_string()));
+                break;
+            };
+            if open > 0 {
+                parts.push(Part::Text(rest[..open].to_string()));
+            }
+            let Some(len) = rest[open + 2..].find("}}") else {
+                return Err(format!(
+                    "unclosed `{{{{` at character {}",
+                    char_offset(text, offset + open)
+                ));
+            };
+            let reference = rest[open + 2..open + 2 + len].trim();
+            let port = reference.parse::<PortRef>().map_err(|_| {
+                format!(
+                    "`{{{{{reference}}}}}` at character {} is not a \"node.port\" reference",
+                    char_offset(text, offset + open)
+                )
+            })?;
+            parts.push(Part::Port(port));
+            rest = &rest[open + 2 + len + 2..];
+        }
+        Ok(ReportTemplate {
+            text: text.to_string(),
+            parts,
+        })
+    }
+}
+
+/// The 1-based character position of byte `offset` in `text`.
+fn char_offset(text: &str, offset: usize) -> usize {
+    text[..offset].chars().count() + 1
+}
+
+impl TryFrom<String> for ReportTemplate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ReportTemplate> for String {
+    fn from(template: ReportTemplate) -> Self {
+        template.text
+    }
+}
+
+impl fmt::Display for ReportTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
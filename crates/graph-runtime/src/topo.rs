@@ -0,0 +1,268 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::ports::EdgeSpec;
+
+/// Perform a Kahn topological sort to ensure deterministic execution order.
+///
+/// Both `dependsOn` entries and edge sources count as dependencies. Nodes
+/// with no pending dependencies are released in declaration order, so the
+/// result is stable for a given `graph.json`.
+pub fn topo_sort(graph: &GraphSpec) -> Result<Vec<&NodeSpec>> {
+    let nodes = &graph.nodes;
+    let mut index_of = HashMap::with_capacity(nodes.len());
+    for (index, node) in nodes.iter().enumerate() {
+        if index_of.insert(node.id.as_str(), index).is_some() {
+            return Err(Error::DuplicateNode(node.id.clone()));
+        }
+    }
+
+    let mut in_degree = vec![0usize; nodes.len()];
+    let mut adjacency = vec![Vec::new(); nodes.len()];
+    for (index, node) in nodes.iter().enumerate() {
+        for dep in graph.dependencies(node) {
+            let Some(&dep_index) = index_of.get(dep.as_str()) else {
+                return Err(Error::UnknownDependency {
+                    node: node.id.clone(),
+                    dependency: dep,
+                });
+            };
+            in_degree[index] += 1;
+            adjacency[dep_index].push(index);
+        }
+    }
+
+    let mut queue: VecDeque<usize> = (0..nodes.len()).filter(|&i| in_degree[i] == 0).collect();
+    let mut result = Vec::with_capacity(nodes.len());
+    while let Some(index) = queue.pop_front() {
+        result.push(&nodes[index]);
+        for &neighbor in &adjacency[index] {
+            in_degree[neighbor] -= 1;
+            if in_degree[neighbor] == 0 {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    if result.len() != nodes.len() {
+        return Err(Error::Cycle {
+            cycles: find_cycles(graph),
+        });
+    }
+
+    Ok(result)
+}
+
+/// A strongly connected component of the dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Every node in the component, in declaration order.
+    pub component: Vec<String>,
+    /// The shortest cycle through the component's first declared node, as
+    /// an ordered edge list: each edge starts where the previous one ended
+    /// and the last one returns to the start.
+    pub edges: Vec<CycleEdge>,
+}
+
+impl Cycle {
+    /// The nodes along [`Cycle::edges`], starting and ending at the same node.
+    pub fn path(&self) -> Vec<&str> {
+        let mut path: Vec<&str> = self.edges.iter().map(|edge| edge.from.as_str()).collect();
+        path.extend(self.edges.first().map(|edge| edge.from.as_str()));
+        path
+    }
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.path().join(" -> "))?;
+        if self.component.len() > self.edges.len() {
+            write!(f, " (component: {})", self.component.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// One dependency along a [`Cycle`]: `to` depends on `from`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleEdge {
+    pub from: String,
+    pub to: String,
+    /// The port edge that creates the dependency, or `None` when it comes
+    /// from `dependsOn`.
+    pub via: Option<EdgeSpec>,
+}
+
+/// Find every strongly connected component that contains a cycle, in the
+/// order their first nodes are declared.
+///
+/// Edges run in data-flow order: `a -> b` means `b` depends on `a`.
+/// Unknown dependencies are ignored and duplicate ids resolve to their
+/// first declaration.
+pub fn find_cycles(graph: &GraphSpec) -> Vec<Cycle> {
+    let nodes = &graph.nodes;
+    let mut index_of = HashMap::with_capacity(nodes.len());
+    for (index, node) in nodes.iter().enumerate() {
+        index_of.entry(node.id.as_str()).or_insert(index);
+    }
+    let mut successors = vec![Vec::new(); nodes.len()];
+    for (index, node) in nodes.iter().enumerate() {
+        for dep in graph.dependencies(node) {
+            if let Some(&dep_index) = index_of.get(dep.as_str()) {
+                successors[dep_index].push(index);
+            }
+        }
+    }
+
+    let mut components = strongly_connected_components(&successors);
+    for component in &mut components {
+        component.sort_unstable();
+    }
+    components.sort_unstable_by_key(|component| component[0]);
+    components
+        .into_iter()
+        .filter_map(|component| {
+            let path = shortest_cycle(&successors, &component, component[0])?;
+            let edges = path
+                .iter()
+                .zip(path.iter().cycle().skip(1))
+                .map(|(&from, &to)| cycle_edge(graph, &nodes[from].id, &nodes[to].id))
+                .collect();
+            Some(Cycle {
+                component: component
+                    .iter()
+                    .map(|&index| nodes[index].id.clone())
+                    .collect(),
+                edges,
+            })
+        })
+        .collect()
+}
+
+fn cycle_edge(graph: &GraphSpec, from: &str, to: &str) -> CycleEdge {
+    let via = graph
+        .edges
+        .iter()
+        .find(|edge| edge.from.node == from && edge.to.node == to)
+        .cloned();
+    CycleEdge {
+        from: from.to_string(),
+        to: to.to_string(),
+        via,
+    }
+}
+
+/// Tarjan's algorithm, returning components in completion order.
+///
+/// The depth-first search keeps its own stack of `(node, next successor)`
+/// frames rather than recursing, so a long dependency chain cannot overflow
+/// the thread's stack.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Tarjan {
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan {
+        fn enter(&mut self, node: usize) {
+            self.index[node] = Some(self.next);
+            self.low[node] = self.next;
+            self.next += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+        }
+
+        fn leave(&mut self, node: usize) {
+            if Some(self.low[node]) == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let count = successors.len();
+    let mut tarjan = Tarjan {
+        index: vec![None; count],
+        low: vec![0; count],
+        on_stack: vec![false; count],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    let mut frames: Vec<(usize, usize)> = Vec::new();
+    for root in 0..count {
+        if tarjan.index[root].is_some() {
+            continue;
+        }
+        tarjan.enter(root);
+        frames.push((root, 0));
+        while let Some((node, position)) = frames.last_mut() {
+            let node = *node;
+            if let Some(&next) = successors[node].get(*position) {
+                *position += 1;
+                match tarjan.index[next] {
+                    None => {
+                        tarjan.enter(next);
+                        frames.push((next, 0));
+                    }
+                    Some(index) if tarjan.on_stack[next] => {
+                        tarjan.low[node] = tarjan.low[node].min(index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                tarjan.low[parent] = tarjan.low[parent].min(tarjan.low[node]);
+            }
+            tarjan.leave(node);
+        }
+    }
+    tarjan.components
+}
+
+/// Breadth-first search for the shortest path from `start` back to itself
+/// that stays inside `component`.
+fn shortest_cycle(
+    successors: &[Vec<usize>],
+    component: &[usize],
+    start: usize,
+) -> Option<Vec<usize>> {
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &next in &successors[node] {
+            if !component.contains(&next) {
+                continue;
+            }
+            if next == start {
+                let mut path = vec![node];
+                while let Some(&previous) = parent.get(path.last()?) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if let Entry::Vacant(entry) = parent.entry(next) {
+                entry.insert(node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,358 @@
+//! Machine-readable traces of a graph run.
+//!
+//! [`format_trace`] renders one JSON object per line: a `node` event for
+//! every node that finished, in topological order, followed by a `failure`
+//! event when the run failed. [`read_trace`] reads such a file back.
+//! [`format_chrome_trace`] renders the same run in Chrome's `trace_event`
+//! format, so it can be opened in `chrome://tracing` or Perfetto with one
+//! track per worker.
+//!
+//! Payloads are written as readable JSON: integers as numbers, UTF-8 byte
+//! payloads as strings and any other bytes as an array of byte values.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::abi::AbiVersion;
+use crate::error::{Error, Result};
+use crate::executor::{ExecutionRecord, ExecutionState};
+use crate::failure::{FailureKind, Frame, NodeError, Outcome};
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::log::LogLine;
+use crate::payload::Payload;
+use crate::ports::PortValues;
+
+/// One line of a JSON Lines trace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum TraceEvent {
+    /// A node that finished, whatever its outcome.
+    Node(Box<NodeEvent>),
+    /// The error a failed run stopped with.
+    Failure(TracedError),
+}
+
+/// What a trace records about one node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeEvent {
+    pub node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abi: Option<AbiVersion>,
+    /// Hex SHA-256 of the node's wasm module.
+    pub wasm_hash: String,
+    /// The node's `config`, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Map<String, Value>>,
+    pub outcome: Outcome,
+    pub attempts: u32,
+    pub dependencies: Vec<String>,
+    /// Hex SHA-256 of the node's ports and wiring; empty in older traces.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub wiring_hash: String,
+    pub inputs: Map<String, Value>,
+    pub outputs: Map<String, Value>,
+    pub worker: usize,
+    /// Microseconds since the start of the run.
+    pub started_us: u64,
+    pub finished_us: u64,
+    /// Whether the outputs came from the result cache.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// Whether the outputs were reused from the previous run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<TracedError>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<LogLine>,
+}
+
+impl NodeEvent {
+    pub fn duration(&self) -> Duration {
+        Duration::from_micros(self.finished_us.saturating_sub(self.started_us))
+    }
+
+    /// The recorded inputs and outputs as port values, if they can be read
+    /// back.
+    pub(crate) fn values(&self) -> Option<(PortValues, PortValues)> {
+        Some((
+            values_from_json(&self.inputs)?,
+            values_from_json(&self.outputs)?,
+        ))
+    }
+}
+
+/// A [`NodeError`] with readable inputs, or just the message of an error
+/// that was not caused by a node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracedError {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<FailureKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i32>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub inputs: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backtrace: Vec<Frame>,
+}
+
+impl From<&NodeError> for TracedError {
+    fn from(error: &NodeError) -> Self {
+        TracedError {
+            node: Some(error.node.clone()),
+            kind: Some(error.kind),
+            code: error.code,
+            message: error.message.clone(),
+            inputs: values_json(&error.inputs),
+            backtrace: error.backtrace.clone(),
+        }
+    }
+}
+
+impl From<&Error> for TracedError {
+    fn from(err: &Error) -> Self {
+        match NodeError::from_error(err, &PortValues::new()) {
+            Some(error) => TracedError::from(&error),
+            None => TracedError {
+                node: None,
+                kind: None,
+                code: None,
+                message: err.to_string(),
+                inputs: Map::new(),
+                backtrace: Vec::new(),
+            },
+        }
+    }
+}
+
+/// A trace read back by [`read_trace`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunTrace {
+    /// The last `node` event of each node, in the order nodes first appear.
+    pub nodes: IndexMap<String, NodeEvent>,
+    /// The last `failure` event, if any.
+    pub failure: Option<TracedError>,
+}
+
+impl RunTrace {
+    /// The previous execution of `node`, if it may stand in for running the
+    /// node again: it succeeded, the node's dependencies, port declarations
+    /// and wiring are the same, each dependency has produced the same
+    /// outputs in `state` as it did then and the ports fed by graph inputs
+    /// received the same values from `graph_inputs`. The caller still has to
+    /// check that the node's module and `config` are unchanged.
+    pub(crate) fn reusable(
+        &self,
+        graph: &GraphSpec,
+        node: &NodeSpec,
+        state: &ExecutionState,
+        graph_inputs: &PortValues,
+    ) -> Option<&NodeEvent> {
+        let event = self.nodes.get(&node.id)?;
+        let dependencies = graph.dependencies(node);
+        if event.outcome != Outcome::Succeeded
+            || event.dependencies != dependencies
+            || event.wiring_hash != graph.wiring_hash(node)
+        {
+            return None;
+        }
+        for input in &graph.inputs {
+            for port in input.to.iter().filter(|port| port.node == node.id) {
+                let value = graph_inputs.get(&input.name).map(payload_json);
+                if value.as_ref() != event.inputs.get(&port.port) {
+                    return None;
+                }
+            }
+        }
+        let unchanged = |dependency: &String| {
+            let (Some(record), Some(before)) = (state.get(dependency), self.nodes.get(dependency))
+            else {
+                return false;
+            };
+            values_json(&record.outputs) == before.outputs
+        };
+        dependencies.iter().all(unchanged).then_some(event)
+    }
+}
+
+/// The trace events describing `state`, followed by the error a failed run
+/// stopped with.
+fn trace_events(state: &ExecutionState, failure: Option<&Error>) -> Vec<TraceEvent> {
+    let mut events: Vec<TraceEvent> = state
+        .iter()
+        .map(|(id, record)| TraceEvent::Node(Box::new(node_event(id, record))))
+        .collect();
+    if let Some(err) = failure {
+        events.push(TraceEvent::Failure(err.into()));
+    }
+    events
+}
+
+/// Render `state`, and the error a failed run stopped with, as JSON Lines.
+pub fn format_trace(state: &ExecutionState, failure: Option<&Error>) -> String {
+    trace_events(state, failure)
+        .iter()
+        .map(|event| serde_json::to_string(event).expect("trace events serialize") + "\n")
+        .collect()
+}
+
+/// Read a JSON Lines trace written by [`format_trace`].
+pub fn read_trace(path: impl AsRef<Path>) -> Result<RunTrace> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    let mut trace = RunTrace::default();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(line).map_err(|source| Error::Trace {
+            path: path.to_path_buf(),
+            line: index + 1,
+            source,
+        })?;
+        match event {
+            TraceEvent::Node(node) => {
+                trace.nodes.insert(node.node.clone(), *node);
+            }
+            TraceEvent::Failure(failure) => trace.failure = Some(failure),
+        }
+    }
+    Ok(trace)
+}
+
+/// Render `state` as a Chrome `trace_event` JSON document: a complete
+/// (`"ph": "X"`) event per node on its worker's track, and a global instant
+/// event when the run failed.
+pub fn format_chrome_trace(state: &ExecutionState, failure: Option<&Error>) -> String {
+    let mut workers: Vec<usize> = state.values().map(|record| record.timing.worker).collect();
+    workers.sort_unstable();
+    workers.dedup();
+    let mut events: Vec<Value> = workers
+        .iter()
+        .map(|worker| {
+            json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": 1,
+                "tid": worker,
+                "args": { "name": format!("worker {worker}") },
+            })
+        })
+        .collect();
+    events.extend(state.iter().map(|(id, record)| chrome_event(id, record)));
+    if let Some(err) = failure {
+        let end = state
+            .values()
+            .map(|record| record.timing.finished)
+            .max()
+            .unwrap_or_default();
+        events.push(json!({
+            "name": "failure",
+            "ph": "i",
+            "s": "g",
+            "ts": micros(end),
+            "pid": 1,
+            "tid": 0,
+            "args": TracedError::from(err),
+        }));
+    }
+    json!({ "traceEvents": events, "displayTimeUnit": "ms" }).to_string()
+}
+
+fn node_event(id: &str, record: &ExecutionRecord) -> NodeEvent {
+    NodeEvent {
+        node: id.to_string(),
+        abi: record.abi,
+        wasm_hash: record.wasm_hash.clone(),
+        config: record.config.clone(),
+        outcome: record.outcome,
+        attempts: record.attempts,
+        dependencies: record.dependencies.clone(),
+        wiring_hash: record.wiring_hash.clone(),
+        inputs: values_json(&record.inputs),
+        outputs: values_json(&record.outputs),
+        worker: record.timing.worker,
+        started_us: micros(record.timing.started),
+        finished_us: micros(record.timing.finished),
+        cached: record.cached,
+        reused: record.reused,
+        error: record.error.as_ref().map(TracedError::from),
+        logs: record.logs.clone(),
+    }
+}
+
+fn chrome_event(id: &str, record: &ExecutionRecord) -> Value {
+    let mut args = json!({
+        "wasmHash": record.wasm_hash,
+        "outcome": record.outcome,
+        "attempts": record.attempts,
+        "cached": record.cached,
+        "reused": record.reused,
+        "inputs": values_json(&record.inputs),
+        "outputs": values_json(&record.outputs),
+    });
+    if let Some(error) = &record.error {
+        args["error"] = json!(TracedError::from(error));
+    }
+    json!({
+        "name": id,
+        "cat": "node",
+        "ph": "X",
+        "ts": micros(record.timing.started),
+        "dur": micros(record.timing.duration()),
+        "pid": 1,
+        "tid": record.timing.worker,
+        "args": args,
+    })
+}
+
+fn values_json(values: &PortValues) -> Map<String, Value> {
+    values
+        .iter()
+        .map(|(name, value)| (name.clone(), payload_json(value)))
+        .collect()
+}
+
+fn values_from_json(values: &Map<String, Value>) -> Option<PortValues> {
+    values
+        .iter()
+        .map(|(name, value)| Some((name.clone(), payload_from_json(value)?)))
+        .collect()
+}
+
+fn payload_json(payload: &Payload) -> Value {
+    match payload {
+        Payload::Int(value) => json!(value),
+        Payload::Bytes(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) => json!(text),
+            Err(_) => json!(bytes),
+        },
+    }
+}
+
+/// The inverse of [`payload_json`].
+fn payload_from_json(value: &Value) -> Option<Payload> {
+    match value {
+        Value::Number(number) => Some(Payload::Int(i32::try_from(number.as_i64()?).ok()?)),
+        Value::String(text) => Some(Payload::from(text.as_str())),
+        Value::Array(bytes) => bytes
+            .iter()
+            .map(|byte| u8::try_from(byte.as_u64()?).ok())
+            .collect::<Option<Vec<u8>>>()
+            .map(Payload::Bytes),
+        _ => None,
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,846 @@
+//! Strict validation of `graph.json` against the published schema.
+//!
+//! The validator walks the whole document and reports every problem it
+//! finds — unknown or missing keys, values of the wrong type, duplicate ids,
+//! dangling dependencies, port wiring errors, malformed report templates,
+//! cycles and (for [`validate_graph`]) missing wasm files — each with the
+//! file, line and column it points at. The structural rules mirror
+//! [`GRAPH_SCHEMA`]. Graph files embedded by subgraph nodes are validated
+//! along the way, and their problems reported with their own file.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+use crate::graph::{GraphSpec, NodeSpec};
+use crate::interface::{GraphInput, GraphOutput};
+use crate::limits::Limits;
+use crate::locate::{escape_segment, Position, SourceMap};
+use crate::ports::{check_ports, EdgeSpec, PortDirection, PortRef, PortSite, PortType};
+use crate::sandbox::{Capability, Sandbox, MAX_STACK_BYTES};
+use crate::subgraph;
+use crate::template::ReportTemplate;
+use crate::topo::find_cycles;
+
+/// JSON Schema (draft 2020-12) describing the graph file format.
+pub const GRAPH_SCHEMA: &str = include_str!("../schema/graph.schema.json");
+
+const GRAPH_KEYS: &[&str] = &[
+    "$schema", "nodes", "edges", "inputs", "outputs", "limits", "sandbox", "report",
+];
+const NODE_KEYS: &[&str] = &[
+    "id",
+    "wasm",
+    "dependsOn",
+    "inputs",
+    "outputs",
+    "limits",
+    "sandbox",
+    "onError",
+    "pure",
+    "config",
+];
+const SUBGRAPH_NODE_KEYS: &[&str] = &["id", "graph", "dependsOn", "limits"];
+/// Keys describing a single module, which a subgraph node does not have.
+const MODULE_NODE_KEYS: &[&str] = &["inputs", "outputs", "sandbox", "onError", "pure", "config"];
+const LIMIT_KEYS: &[&str] = &["fuel", "timeoutMs"];
+const SANDBOX_KEYS: &[&str] = &[
+    "maxMemoryPages",
+    "maxTableElements",
+    "maxStackBytes",
+    "allow",
+    "preopens",
+];
+const PREOPEN_KEYS: &[&str] = &["host", "guest", "writable"];
+const ON_ERROR_KEYS: &[&str] = &["default", "retry"];
+const ON_ERROR: &str = "\"fail\", \"skip\", { \"default\": value } or { \"retry\": count }";
+const CAPABILITIES: &str = "one of \"log\", \"clock\", \"random\", \"fs\"";
+const PORT_KEYS: &[&str] = &["name", "type", "required"];
+const EDGE_KEYS: &[&str] = &["from", "to"];
+const GRAPH_INPUT_KEYS: &[&str] = &["name", "type", "to", "default"];
+const GRAPH_OUTPUT_KEYS: &[&str] = &["name", "from"];
+const PORT_TYPES: &str = "one of \"any\", \"i32\", \"string\", \"json\", \"bytes\"";
+
+/// A single validation problem and where it was found.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub position: Position,
+    /// JSON pointer to the offending value, e.g. `/nodes/1/dependsOn/0`.
+    pub pointer: String,
+    pub error: Error,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.file.display(),
+            self.position,
+            self.error
+        )
+    }
+}
+
+/// Every problem found in a graph specification, ordered by position.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.diagnostics.len();
+        write!(
+            f,
+            "graph specification has {count} problem{}:",
+            if count == 1 { "" } else { "s" }
+        )?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n  {diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate the graph at `graph_path`, including that every referenced wasm
+/// file exists, and return the parsed specification if it is clean.
+pub fn validate_graph(graph_path: impl AsRef<Path>) -> Result<GraphSpec> {
+    load(graph_path.as_ref(), true)
+}
+
+/// Read, parse and validate a graph file. Syntax errors are reported on
+/// their own since nothing else can be checked; everything else is
+/// collected into a single [`Error::Invalid`].
+pub(crate) fn load(graph_path: &Path, check_files: bool) -> Result<GraphSpec> {
+    let mut including = fs::canonicalize(graph_path).into_iter().collect();
+    load_included(graph_path, check_files, &mut including)
+}
+
+/// [`load`] a graph file embedded by the files in `including`, and flatten
+/// the subgraph nodes it has in turn.
+fn load_included(
+    graph_path: &Path,
+    check_files: bool,
+    including: &mut Vec<PathBuf>,
+) -> Result<GraphSpec> {
+    let text = fs::read_to_string(graph_path).map_err(|err| Error::io(graph_path, err))?;
+    let value: Value = serde_json::from_str(&text).map_err(|source| Error::Parse {
+        path: graph_path.to_path_buf(),
+        source,
+    })?;
+
+    let mut validator = Validator {
+        file: graph_path,
+        source: SourceMap::new(&text),
+        diagnostics: Vec::new(),
+        check_files,
+        including,
+    };
+    let graph = validator.document(&value);
+    if check_files {
+        let base_dir = graph_path.parent().unwrap_or_else(|| Path::new("."));
+        validator.wasm_files(&graph, base_dir);
+    }
+
+    if validator.diagnostics.is_empty() {
+        return Ok(subgraph::flatten(graph.spec, &graph.subgraphs));
+    }
+    let mut diagnostics = validator.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.position);
+    Err(Error::Invalid(ValidationReport { diagnostics }))
+}
+
+/// The well-formed parts of the document, with the index each node and edge
+/// had in the source so semantic problems can be located.
+struct Parsed {
+    spec: GraphSpec,
+    node_sources: Vec<usize>,
+    edge_sources: Vec<usize>,
+    /// The flattened graph each subgraph node embeds, keyed by node id.
+    subgraphs: HashMap<String, GraphSpec>,
+}
+
+struct Validator<'a> {
+    file: &'a Path,
+    source: SourceMap,
+    diagnostics: Vec<Diagnostic>,
+    check_files: bool,
+    /// Canonical paths of the graph files being loaded, outermost first.
+    including: &'a mut Vec<PathBuf>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, pointer: String, error: Error) {
+        let position = self.source.value(&pointer).unwrap_or_default();
+        self.push(pointer, position, error);
+    }
+
+    fn report_key(&mut self, pointer: String, error: Error) {
+        let position = self.source.key(&pointer).unwrap_or_default();
+        self.push(pointer, position, error);
+    }
+
+    fn push(&mut self, pointer: String, position: Position, error: Error) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.to_path_buf(),
+            position,
+            pointer,
+            error,
+        });
+    }
+
+    fn document(&mut self, root: &Value) -> Parsed {
+        let mut parsed = Parsed {
+            spec: GraphSpec::default(),
+            node_sources: Vec::new(),
+            edge_sources: Vec::new(),
+            subgraphs: HashMap::new(),
+        };
+        let Some(object) = self.object(root, String::new(), GRAPH_KEYS) else {
+            return parsed;
+        };
+
+        if let Some(limits) = object.get("limits") {
+            parsed.spec.limits = self.limits(limits, "/limits".to_string());
+        }
+        if let Some(sandbox) = object.get("sandbox") {
+            parsed.spec.sandbox = self.sandbox(sandbox, "/sandbox".to_string());
+        }
+        if let Some(report) = object.get("report") {
+            parsed.spec.report = self.report_template(report, "/report".to_string());
+        }
+
+        let mut complete = true;
+        match object.get("nodes").and_then(Value::as_array) {
+            Some(nodes) => {
+                for (index, node) in nodes.iter().enumerate() {
+                    let pointer = format!("/nodes/{index}");
+                    match self.node(node, pointer.clone()) {
+                        Some(mut node) => {
+                            if let Some(path) = &node.graph {
+                                let path = self.file.parent().unwrap_or(Path::new(".")).join(path);
+                                match self.subgraph(&path, format!("{pointer}/graph")) {
+                                    Some(child) => {
+                                        (node.inputs, node.outputs) = subgraph::ports(&child);
+                                        parsed.subgraphs.insert(node.id.clone(), child);
+                                    }
+                                    None => complete = false,
+                                }
+                            }
+                            parsed.spec.nodes.push(node);
+                            parsed.node_sources.push(index);
+                        }
+                        None => complete = false,
+                    }
+                }
+            }
+            None => {
+                self.report(String::new(), Error::MissingNodes);
+                complete = false;
+            }
+        }
+
+        if let Some(edges) = object.get("edges") {
+            match self.array(edges, "/edges".to_string()) {
+                Some(edges) => {
+                    for (index, edge) in edges.iter().enumerate() {
+                        match self.edge(edge, format!("/edges/{index}")) {
+                            Some(edge) => {
+                                parsed.spec.edges.push(edge);
+                                parsed.edge_sources.push(index);
+                            }
+                            None => complete = false,
+                        }
+                    }
+                }
+                None => complete = false,
+            }
+        }
+
+        if let Some(inputs) = object.get("inputs") {
+            match self.array(inputs, "/inputs".to_string()) {
+                Some(inputs) => {
+                    for (index, input) in inputs.iter().enumerate() {
+                        match self.graph_input(input, format!("/inputs/{index}")) {
+                            Some(input) => parsed.spec.inputs.push(input),
+                            None => complete = false,
+                        }
+                    }
+                }
+                None => complete = false,
+            }
+        }
+
+        if let Some(outputs) = object.get("outputs") {
+            match self.array(outputs, "/outputs".to_string()) {
+                Some(outputs) => {
+                    for (index, output) in outputs.iter().enumerate() {
+                        match self.graph_output(output, format!("/outputs/{index}")) {
+                            Some(output) => parsed.spec.outputs.push(output),
+                            None => complete = false,
+                        }
+                    }
+                }
+                None => complete = false,
+            }
+        }
+
+        // Cross-references are only meaningful once every node and edge has
+        // loaded; a malformed node would otherwise surface again as dangling
+        // dependencies or unknown edge endpoints.
+        if complete {
+            self.semantics(&parsed);
+        }
+        parsed
+    }
+
+    fn node(&mut self, value: &Value, pointer: String) -> Option<NodeSpec> {
+        let embeds = value.get("graph").is_some();
+        let keys: Vec<&'static str> = if embeds {
+            [SUBGRAPH_NODE_KEYS, MODULE_NODE_KEYS].concat()
+        } else {
+            NODE_KEYS.to_vec()
+        };
+        let object = self.object(value, pointer.clone(), &keys)?;
+        // Unknown keys are reported but do not stop the node from loading.
+        let before = self.diagnostics.len();
+        if embeds {
+            for &key in MODULE_NODE_KEYS {
+                if object.contains_key(key) {
+                    self.report_key(format!("{pointer}/{key}"), Error::SubgraphNodeKey(key));
+                }
+            }
+        }
+        for key in ["id", if embeds { "graph" } else { "wasm" }] {
+            match object.get(key) {
+                Some(value) => {
+                    self.string(value, format!("{pointer}/{key}"));
+                }
+                None => self.report(pointer.clone(), Error::MissingKey(key)),
+            }
+        }
+        if let Some(deps) = object.get("dependsOn") {
+            let deps_pointer = format!("{pointer}/dependsOn");
+            let deps = self.array(deps, deps_pointer.clone()).unwrap_or_default();
+            for (index, dep) in deps.iter().enumerate() {
+                self.string(dep, format!("{deps_pointer}/{index}"));
+            }
+        }
+        for key in ["inputs", "outputs"] {
+            if let Some(ports) = object.get(key) {
+                let ports_pointer = format!("{pointer}/{key}");
+                let ports = self.array(ports, ports_pointer.clone()).unwrap_or_default();
+                for (index, port) in ports.iter().enumerate() {
+                    self.port(port, format!("{ports_pointer}/{index}"));
+                }
+            }
+        }
+        if let Some(limits) = object.get("limits") {
+            self.limits(limits, format!("{pointer}/limits"));
+        }
+        if let Some(sandbox) = object.get("sandbox") {
+            self.sandbox(sandbox, format!("{pointer}/sandbox"));
+        }
+        if let Some(on_error) = object.get("onError") {
+            self.on_error(on_error, format!("{pointer}/onError"));
+        }
+        if let Some(pure) = object.get("pure") {
+            if !pure.is_boolean() {
+                self.invalid(pure, format!("{pointer}/pure"), "a boolean");
+            }
+        }
+        if let Some(config) = object.get("config") {
+            if !config.is_object() {
+                self.invalid(config, format!("{pointer}/config"), "an object");
+            }
+        }
+        if self.diagnostics.len() != before {
+            return None;
+        }
+        let node: NodeSpec = serde_json::from_value(value.clone()).ok()?;
+        if let Some(Err(reason)) = node.on_error.default_outputs(&node) {
+            self.report(
+                format!("{pointer}/onError/default"),
+                Error::InvalidDefault {
+                    node: node.id.clone(),
+                    reason,
+                },
+            );
+            return None;
+        }
+        Some(node)
+    }
+
+    fn on_error(&mut self, value: &Value, pointer: String) {
+        if let Some(policy) = value.as_str() {
+            if !matches!(policy, "fail" | "skip") {
+                self.invalid(value, pointer, ON_ERROR);
+            }
+            return;
+        }
+        let Some(object) = self.object(value, pointer.clone(), ON_ERROR_KEYS) else {
+            return;
+        };
+        let policies = ON_ERROR_KEYS
+            .iter()
+            .filter(|key| object.contains_key(**key))
+            .count();
+        if policies != 1 {
+            self.invalid(value, pointer, ON_ERROR);
+            return;
+        }
+        if let Some(retry) = object.get("retry") {
+            if retry
+                .as_u64()
+                .is_none_or(|n| n == 0 || u32::try_from(n).is_err())
+            {
+                self.invalid(retry, format!("{pointer}/retry"), "a positive integer");
+            }
+        }
+    }
+
+    fn limits(&mut self, value: &Value, pointer: String) -> Option<Limits> {
+        let object = self.object(value, pointer.clone(), LIMIT_KEYS)?;
+        let before = self.diagnostics.len();
+        for (key, value) in object {
+            if LIMIT_KEYS.contains(&key.as_str()) && value.as_u64().is_none_or(|n| n == 0) {
+                self.invalid(value, format!("{pointer}/{key}"), "a positive integer");
+            }
+        }
+        if self.diagnostics.len() != before {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    fn sandbox(&mut self, value: &Value, pointer: String) -> Option<Sandbox> {
+        let object = self.object(value, pointer.clone(), SANDBOX_KEYS)?;
+        let before = self.diagnostics.len();
+        let bounds = [
+            ("maxMemoryPages", 65536, "an integer between 1 and 65536"),
+            (
+                "maxTableElements",
+                u64::from(u32::MAX),
+                "a positive 32-bit integer",
+            ),
+            (
+                "maxStackBytes",
+                MAX_STACK_BYTES,
+                "an integer between 1 and 1048576",
+            ),
+        ];
+        for (key, max, expected) in bounds {
+            if let Some(value) = object.get(key) {
+                if value.as_u64().is_none_or(|n| n == 0 || n > max) {
+                    self.invalid(value, format!("{pointer}/{key}"), expected);
+                }
+            }
+        }
+        if let Some(allow) = object.get("allow") {
+            let allow_pointer = format!("{pointer}/allow");
+            let allow = self.array(allow, allow_pointer.clone()).unwrap_or_default();
+            for (index, capability) in allow.iter().enumerate() {
+                if serde_json::from_value::<Capability>(capability.clone()).is_err() {
+                    self.invalid(capability, format!("{allow_pointer}/{index}"), CAPABILITIES);
+                }
+            }
+        }
+        if let Some(preopens) = object.get("preopens") {
+            let preopens_pointer = format!("{pointer}/preopens");
+            let preopens = self
+                .array(preopens, preopens_pointer.clone())
+                .unwrap_or_default();
+            for (index, preopen) in preopens.iter().enumerate() {
+                self.preopen(preopen, format!("{preopens_pointer}/{index}"));
+            }
+        }
+        if self.diagnostics.len() != before {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    fn preopen(&mut self, value: &Value, pointer: String) {
+        let Some(object) = self.object(value, pointer.clone(), PREOPEN_KEYS) else {
+            return;
+        };
+        for key in ["host", "guest"] {
+            match object.get(key) {
+                Some(value) => {
+                    self.string(value, format!("{pointer}/{key}"));
+                }
+                None => self.report(pointer.clone(), Error::MissingKey(key)),
+            }
+        }
+        if let Some(writable) = object.get("writable") {
+            if !writable.is_boolean() {
+                self.invalid(writable, format!("{pointer}/writable"), "a boolean");
+            }
+        }
+    }
+
+    fn port(&mut self, value: &Value, pointer: String) {
+        let Some(object) = self.object(value, pointer.clone(), PORT_KEYS) else {
+            return;
+        };
+        match object.get("name") {
+            Some(name) => {
+                self.string(name, format!("{pointer}/name"));
+            }
+            None => self.report(pointer.clone(), Error::MissingKey("name")),
+        }
+        if let Some(ty) = object.get("type") {
+            if serde_json::from_value::<PortType>(ty.clone()).is_err() {
+                self.invalid(ty, format!("{pointer}/type"), PORT_TYPES);
+            }
+        }
+        if let Some(required) = object.get("required") {
+            if !required.is_boolean() {
+                self.invalid(required, format!("{pointer}/required"), "a boolean");
+            }
+        }
+    }
+
+    fn edge(&mut self, value: &Value, pointer: String) -> Option<EdgeSpec> {
+        let object = self.object(value, pointer.clone(), EDGE_KEYS)?;
+        let before = self.diagnostics.len();
+        for key in ["from", "to"] {
+            match object.get(key) {
+                Some(end) => self.port_ref(end, format!("{pointer}/{key}")),
+                None => self.report(pointer.clone(), Error::MissingKey(key)),
+            }
+        }
+        if self.diagnostics.len() != before {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    fn report_template(&mut self, value: &Value, pointer: String) -> Option<ReportTemplate> {
+        match self.string(value, pointer.clone())?.parse() {
+            Ok(template) => Some(template),
+            Err(reason) => {
+                self.report(pointer, Error::InvalidTemplate(reason));
+                None
+            }
+        }
+    }
+
+    fn graph_input(&mut self, value: &Value, pointer: String) -> Option<GraphInput> {
+        let object = self.object(value, pointer.clone(), GRAPH_INPUT_KEYS)?;
+        let before = self.diagnostics.len();
+        match object.get("name") {
+            Some(name) => {
+                self.string(name, format!("{pointer}/name"));
+            }
+            None => self.report(pointer.clone(), Error::MissingKey("name")),
+        }
+        if let Some(ty) = object.get("type") {
+            if serde_json::from_value::<PortType>(ty.clone()).is_err() {
+                self.invalid(ty, format!("{pointer}/type"), PORT_TYPES);
+            }
+        }
+        match object.get("to") {
+            Some(to) => {
+                let to_pointer = format!("{pointer}/to");
+                let targets = self.array(to, to_pointer.clone()).unwrap_or_default();
+                for (index, target) in targets.iter().enumerate() {
+                    self.port_ref(target, format!("{to_pointer}/{index}"));
+                }
+            }
+            None => self.report(pointer.clone(), Error::MissingKey("to")),
+        }
+        if self.diagnostics.len() != before {
+            return None;
+        }
+        let input: GraphInput = serde_json::from_value(value.clone()).ok()?;
+        if let Some(Err(err)) = input.default.as_ref().map(|value| input.decode(value)) {
+            self.report(format!("{pointer}/default"), err);
+            return None;
+        }
+        Some(input)
+    }
+
+    fn graph_output(&mut self, value: &Value, pointer: String) -> Option<GraphOutput> {
+        let object = self.object(value, pointer.clone(), GRAPH_OUTPUT_KEYS)?;
+        let before = self.diagnostics.len();
+        match object.get("name") {
+            Some(name) => {
+                self.string(name, format!("{pointer}/name"));
+            }
+            None => self.report(pointer.clone(), Error::MissingKey("name")),
+        }
+        match object.get("from") {
+            Some(from) => self.port_ref(from, format!("{pointer}/from")),
+            None => self.report(pointer.clone(), Error::MissingKey("from")),
+        }
+        if self.diagnostics.len() != before {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    /// Report duplicate ids and graph input or output names, dangling
+    /// dependencies, port wiring problems and cycles among the well-formed
+    /// nodes, edges, inputs and outputs.
+    fn semantics(&mut self, parsed: &Parsed) {
+        let graph = &parsed.spec;
+        let node_pointer = |index: usize| format!("/nodes/{}", parsed.node_sources[index]);
+
+        let mut seen = HashSet::new();
+        for (index, node) in graph.nodes.iter().enumerate() {
+            if !seen.insert(node.id.as_str()) {
+                self.report(
+                    format!("{}/id", node_pointer(index)),
+                    Error::DuplicateNode(node.id.clone()),
+                );
+            }
+        }
+        // Flattening namespaces embedded ids, which must not collide with
+        // the ids already in this graph.
+        let mut embedded = HashSet::new();
+        for (index, node) in graph.nodes.iter().enumerate() {
+            let Some(child) = parsed.subgraphs.get(&node.id) else {
+                continue;
+            };
+            for inner in &child.nodes {
+                let id = format!("{}/{}", node.id, inner.id);
+                if seen.contains(id.as_str()) || !embedded.insert(id.clone()) {
+                    self.report(
+                        format!("{}/id", node_pointer(index)),
+                        Error::DuplicateNode(id),
+                    );
+                }
+            }
+        }
+
+        for (index, node) in graph.nodes.iter().enumerate() {
+            for (dep_index, dep) in node.depends_on.iter().enumerate() {
+                if !seen.contains(dep.as_str()) {
+                    self.report(
+                        format!("{}/dependsOn/{dep_index}", node_pointer(index)),
+                        Error::UnknownDependency {
+                            node: node.id.clone(),
+                            dependency: dep.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for (index, input) in graph.inputs.iter().enumerate() {
+            if !names.insert(input.name.as_str()) {
+                self.report(
+                    format!("/inputs/{index}/name"),
+                    Error::DuplicateGraphPort {
+                        name: input.name.clone(),
+                        direction: PortDirection::Input,
+                    },
+                );
+            }
+        }
+        let mut names = HashSet::new();
+        for (index, output) in graph.outputs.iter().enumerate() {
+            if !names.insert(output.name.as_str()) {
+                self.report(
+                    format!("/outputs/{index}/name"),
+                    Error::DuplicateGraphPort {
+                        name: output.name.clone(),
+                        direction: PortDirection::Output,
+                    },
+                );
+            }
+        }
+
+        for (site, error) in check_ports(graph) {
+            let pointer = match site {
+                PortSite::Edge(index, end) => {
+                    format!("/edges/{}/{end}", parsed.edge_sources[index])
+                }
+                PortSite::Input(node, port) => format!("{}/inputs/{port}", node_pointer(node)),
+                PortSite::GraphInput(input, target) => format!("/inputs/{input}/to/{target}"),
+                PortSite::GraphOutput(output) => format!("/outputs/{output}/from"),
+                PortSite::Report => "/report".to_string(),
+            };
+            self.report(pointer, error);
+        }
+
+        for cycle in find_cycles(graph) {
+            let start = &cycle.component[0];
+            let index = graph.nodes.iter().position(|node| &node.id == start);
+            let pointer = index.map(node_pointer).unwrap_or_default();
+            self.report(
+                pointer,
+                Error::Cycle {
+                    cycles: vec![cycle],
+                },
+            );
+        }
+    }
+
+    /// Load the graph file a subgraph node embeds, reporting its problems
+    /// alongside this file's.
+    fn subgraph(&mut self, path: &Path, pointer: String) -> Option<GraphSpec> {
+        let canonical = match fs::canonicalize(path) {
+            Ok(canonical) => canonical,
+            Err(err) => {
+                self.report(pointer, Error::io(path, err));
+                return None;
+            }
+        };
+        if self.including.contains(&canonical) {
+            self.report(pointer, Error::RecursiveSubgraph(path.to_path_buf()));
+            return None;
+        }
+        self.including.push(canonical);
+        let loaded = load_included(path, self.check_files, self.including);
+        self.including.pop();
+        match loaded {
+            Ok(child) if child.outputs.is_empty() => {
+                self.report(pointer, Error::SubgraphOutputs(path.to_path_buf()));
+                None
+            }
+            Ok(child) => Some(child),
+            Err(Error::Invalid(report)) => {
+                self.diagnostics.extend(report.diagnostics);
+                None
+            }
+            Err(err) => {
+                self.report(pointer, err);
+                None
+            }
+        }
+    }
+
+    fn wasm_files(&mut self, parsed: &Parsed, base_dir: &Path) {
+        for (index, node) in parsed.spec.nodes.iter().enumerate() {
+            if node.graph.is_some() {
+                continue;
+            }
+            let path = base_dir.join(&node.wasm);
+            if !path.is_file() {
+                self.report(
+                    format!("/nodes/{}/wasm", parsed.node_sources[index]),
+                    Error::MissingWasm {
+                        node: node.id.clone(),
+                        path,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Check that `value` is an object with only `allowed` keys.
+    fn object<'v>(
+        &mut self,
+        value: &'v Value,
+        pointer: String,
+        allowed: &[&'static str],
+    ) -> Option<&'v Map<String, Value>> {
+        let Some(object) = value.as_object() else {
+            self.invalid(value, pointer, "an object");
+            return None;
+        };
+        for key in object.keys() {
+            if !allowed.contains(&key.as_str()) {
+                self.report_key(
+                    format!("{pointer}/{}", escape_segment(key)),
+                    Error::UnknownKey {
+                        key: key.clone(),
+                        suggestion: suggest(key, allowed),
+                    },
+                );
+            }
+        }
+        Some(object)
+    }
+
+    fn array<'v>(&mut self, value: &'v Value, pointer: String) -> Option<&'v [Value]> {
+        let array = value.as_array().map(Vec::as_slice);
+        if array.is_none() {
+            self.invalid(value, pointer, "an array");
+        }
+        array
+    }
+
+    /// Check that `value` is a `"node.port"` reference.
+    fn port_ref(&mut self, value: &Value, pointer: String) {
+        if let Some(text) = self.string(value, pointer.clone()) {
+            if text.parse::<PortRef>().is_err() {
+                self.invalid(value, pointer, "a \"node.port\" reference");
+            }
+        }
+    }
+
+    fn string<'v>(&mut self, value: &'v Value, pointer: String) -> Option<&'v str> {
+        match value.as_str() {
+            Some(text) if !text.is_empty() => Some(text),
+            Some(_) => {
+                self.invalid(value, pointer, "a non-empty string");
+                None
+            }
+            None => {
+                self.invalid(value, pointer, "a string");
+                None
+            }
+        }
+    }
+
+    fn invalid(&mut self, value: &Value, pointer: String, expected: &'static str) {
+        let found = match value {
+            Value::Null => "null".to_string(),
+            Value::Bool(_) => "a boolean".to_string(),
+            Value::Number(_) => "a number".to_string(),
+            Value::String(text) => format!("{text:?}"),
+            Value::Array(_) => "an array".to_string(),
+            Value::Object(_) => "an object".to_string(),
+        };
+        self.report(pointer, Error::InvalidValue { expected, found });
+    }
+}
+
+/// Suggest the allowed key closest to a misspelled one.
+fn suggest(key: &str, allowed: &[&'static str]) -> Option<&'static str> {
+    allowed
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|&(distance, candidate)| distance <= 2.max(candidate.len() / 3))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,160 @@
+use std::fs;
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+
+use graph_runtime::{
+    source_hash, BuildManifest, BuiltArtifact, Error, NodeBuild, Runtime, BUILD_MANIFEST,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn manifests_round_trip_and_start_empty() {
+    let path = temp_path("build-manifest.json");
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(
+        BuildManifest::load(&path).unwrap(),
+        BuildManifest::default()
+    );
+
+    let mut manifest = BuildManifest::default();
+    manifest.nodes.insert(
+        "calcDiscount".to_string(),
+        BuiltArtifact {
+            package: "calc-discount".to_string(),
+            artifact: "calcDiscount.wasm".to_string(),
+            hash: "de6f".repeat(16),
+            size: 9640,
+            source: Some("calcDiscount".to_string()),
+            source_hash: Some("7739".repeat(16)),
+            dependencies: IndexMap::from([("Cargo.lock".to_string(), "91c2".repeat(16))]),
+            toolchain: Some("rustc 1.95.0".to_string()),
+        },
+    );
+    manifest.save(&path).unwrap();
+    let loaded = BuildManifest::load(&path).unwrap();
+    std::fs::write(&path, "{ \"nodes\": [] }").unwrap();
+    let err = BuildManifest::load(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, manifest);
+    assert!(matches!(err, Error::Manifest { .. }), "{err}");
+}
+
+#[test]
+fn unknown_nodes_fail_before_building() {
+    let workspace = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../nodes");
+    let err = NodeBuild::new(workspace)
+        .with_nodes(vec!["calcDiscount".to_string(), "nope".to_string()])
+        .run()
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::UnknownNodePackage(ref node) if node == "nope"),
+        "{err}"
+    );
+}
+
+#[test]
+fn runs_refuse_artifacts_that_do_not_match_the_manifest() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/profile");
+    let dir = temp_path("locked");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("fetchUser/src")).unwrap();
+    for entry in fs::read_dir(fixture).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+    }
+    fs::write(dir.join("fetchUser/src/lib.rs"), "// v1\n").unwrap();
+    fs::create_dir_all(dir.join("sdk/src")).unwrap();
+    fs::write(dir.join("sdk/src/lib.rs"), "// v1\n").unwrap();
+    let sdk_hash = source_hash(&dir.join("sdk")).unwrap();
+    let graph = dir.join("graph.json");
+
+    // Lock the modules the unchecked run executed.
+    let state = Runtime::new().run_graph(&graph).unwrap();
+    let mut manifest = BuildManifest::default();
+    for (node, record) in &state {
+        let source = (node == "fetchUser").then(|| node.clone());
+        manifest.nodes.insert(
+            node.clone(),
+            BuiltArtifact {
+                package: node.clone(),
+                artifact: format!("{node}.wat"),
+                hash: record.wasm_hash.clone(),
+                size: 0,
+                source_hash: source
+                    .as_ref()
+                    .map(|source| source_hash(&dir.join(source)).unwrap()),
+                source,
+                dependencies: IndexMap::from([("sdk".to_string(), sdk_hash.clone())]),
+                toolchain: None,
+            },
+        );
+    }
+    manifest.save(dir.join(BUILD_MANIFEST)).unwrap();
+    let runtime = Runtime::new();
+    runtime.run_graph(&graph).unwrap();
+
+    // A dependency shared by every node, like the SDK, changed.
+    fs::write(dir.join("sdk/src/lib.rs"), "// v2\n").unwrap();
+    let dependency = runtime.run_graph(&graph).unwrap_err();
+    assert!(
+        matches!(&dependency, Error::StaleArtifact { reason, .. }
+            if reason.starts_with("its dependency ")
+                && reason.ends_with("sdk changed since it was built")),
+        "{dependency}"
+    );
+    fs::write(dir.join("sdk/src/lib.rs"), "// v1\n").unwrap();
+    runtime.run_graph(&graph).unwrap();
+
+    // Each run checks the manifest afresh, even on the same runtime.
+    fs::write(dir.join("fetchUser/src/lib.rs"), "// v2\n").unwrap();
+    let changed = runtime.run_graph(&graph).unwrap_err();
+    assert!(
+        matches!(&changed, Error::StaleArtifact { node, reason, .. }
+            if node == "fetchUser" && reason.ends_with("changed since it was built")),
+        "{changed}"
+    );
+    Runtime::new()
+        .with_stale_artifacts(true)
+        .run_graph(&graph)
+        .unwrap();
+
+    manifest.nodes.shift_remove("fetchUser");
+    manifest.save(dir.join(BUILD_MANIFEST)).unwrap();
+    let unlisted = Runtime::new().run_graph(&graph).unwrap_err();
+    assert!(
+        unlisted
+            .to_string()
+            .ends_with("the manifest does not list it."),
+        "{unlisted}"
+    );
+    fs::write(
+        dir.join("fetchUser.wat"),
+        "(module (func (export \"main\") (param i32) (result i32) i32.const 1))",
+    )
+    .unwrap();
+    manifest.nodes.insert(
+        "fetchUser".to_string(),
+        BuiltArtifact {
+            package: "fetchUser".to_string(),
+            artifact: "fetchUser.wat".to_string(),
+            hash: state["fetchUser"].wasm_hash.clone(),
+            size: 0,
+            source: None,
+            source_hash: None,
+            dependencies: IndexMap::new(),
+            toolchain: None,
+        },
+    );
+    manifest.save(dir.join(BUILD_MANIFEST)).unwrap();
+    let swapped = Runtime::new().run_graph(&graph).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        matches!(&swapped, Error::StaleArtifact { node, reason, .. }
+            if node == "fetchUser" && reason.starts_with("its SHA-256 is ")),
+        "{swapped}"
+    );
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use graph_runtime::{CacheStats, Runtime};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn artifacts(dir: &PathBuf) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for engine in std::fs::read_dir(dir).unwrap() {
+        for artifact in std::fs::read_dir(engine.unwrap().path()).unwrap() {
+            files.push(artifact.unwrap().path());
+        }
+    }
+    files
+}
+
+#[test]
+fn shared_modules_compile_once_per_runtime() {
+    // The parallel fixture runs spin.wat four times and echo.wat once.
+    let runtime = Runtime::new();
+    runtime.run_graph(fixture("parallel")).unwrap();
+    assert_eq!(
+        runtime.cache_stats(),
+        CacheStats {
+            memory_hits: 3,
+            disk_hits: 0,
+            compiled: 2
+        }
+    );
+
+    runtime.run_graph(fixture("parallel")).unwrap();
+    assert_eq!(runtime.cache_stats().memory_hits, 8);
+    assert_eq!(runtime.cache_stats().compiled, 2);
+
+    let uncached = Runtime::new().without_cache();
+    uncached.run_graph(fixture("parallel")).unwrap();
+    assert_eq!(uncached.cache_stats().compiled, 5);
+    assert_eq!(uncached.cache_stats().hits(), 0);
+}
+
+#[test]
+fn disk_cache_is_reused_between_runtimes() {
+    let dir = cache_dir("disk-cache");
+    let first = Runtime::new().with_disk_cache(&dir);
+    let state = first.run_graph(fixture("profile")).unwrap();
+    assert_eq!(first.cache_stats().compiled, 3);
+    assert_eq!(artifacts(&dir).len(), 3);
+
+    let second = Runtime::new().with_disk_cache(&dir);
+    assert_eq!(
+        second.run_graph(fixture("profile")).unwrap()["renderProfile"].outputs,
+        state["renderProfile"].outputs
+    );
+    assert_eq!(second.cache_stats().disk_hits, 3);
+    assert_eq!(second.cache_stats().compiled, 0);
+
+    // A damaged artifact is recompiled and replaced.
+    for artifact in artifacts(&dir) {
+        std::fs::write(artifact, b"not a module").unwrap();
+    }
+    let third = Runtime::new().with_disk_cache(&dir);
+    third.run_graph(fixture("profile")).unwrap();
+    assert_eq!(third.cache_stats().compiled, 3);
+    let fourth = Runtime::new().with_disk_cache(&dir);
+    fourth.run_graph(fixture("profile")).unwrap();
+    assert_eq!(fourth.cache_stats().disk_hits, 3);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use graph_runtime::{
+    check_node, componentize, run_graph, AbiVersion, Capability, Error, LogLevel, LogLine,
+    NodeBuild, Payload,
+};
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+/// A directory holding the doubler fixture as a component, next to a graph
+/// feeding it `value` from a core module.
+fn doubler_graph(name: &str, value: i32, allow: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let core = fs::read(fixture("component/doubler.wat")).unwrap();
+    fs::write(
+        dir.join("doubler.wasm"),
+        componentize("doubler", &core).unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("source.wat"),
+        format!(r#"(module (func (export "main") (param i32) (result i32) i32.const {value}))"#),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("graph.json"),
+        format!(
+            r#"{{
+              "sandbox": {{ "allow": [{allow}] }},
+              "nodes": [
+                {{ "id": "source", "wasm": "./source.wat" }},
+                {{ "id": "doubler", "wasm": "./doubler.wasm", "dependsOn": ["source"] }}
+              ]
+            }}"#
+        ),
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn component_nodes_run_alongside_core_modules() {
+    let dir = doubler_graph("component", 1001, r#""log""#);
+    let state = run_graph(dir.join("graph.json"));
+    let checked = check_node(dir.join("doubler.wasm"), None, Some(&[]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    let state = state.unwrap();
+    assert_eq!(state["source"].abi, Some(AbiVersion::V1));
+    assert_eq!(state["doubler"].abi, Some(AbiVersion::Component));
+    assert_eq!(state["doubler"].input(), Some(&Payload::Int(1001)));
+    assert_eq!(state["doubler"].output(), Some(&Payload::Int(2002)));
+    assert_eq!(
+        state["doubler"].logs,
+        [LogLine {
+            level: LogLevel::Info,
+            message: "doubling".to_string(),
+        }]
+    );
+
+    let checked = checked.unwrap();
+    assert_eq!(checked.abi, Some(AbiVersion::Component));
+    assert_eq!(checked.capabilities, vec![Capability::Log]);
+    assert_eq!(
+        checked.problems,
+        vec!["It imports log, which needs the `log` capability."]
+    );
+}
+
+#[test]
+fn component_errors_and_imports_are_checked() {
+    let dir = doubler_graph("component-zero", 0, r#""log""#);
+    let reported = run_graph(dir.join("graph.json")).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        matches!(&reported, Error::NodeReported { node, code: 7, message }
+            if node == "doubler" && message == "zero input"),
+        "{reported}"
+    );
+
+    let dir = doubler_graph("component-muted", 1, "");
+    let denied = run_graph(dir.join("graph.json")).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        denied.to_string(),
+        "Node doubler imports log, but its sandbox policy does not allow `log`."
+    );
+}
+
+#[test]
+fn modules_without_the_node_world_glue_cannot_be_componentized() {
+    let err = componentize("echo", &fs::read(fixture("ports/echo.wat")).unwrap()).unwrap_err();
+    assert!(
+        matches!(&err, Error::Componentize { node, .. } if node == "echo"),
+        "{err}"
+    );
+}
+
+/// A graph feeding `value` from a core module to the `scoreTier` node built
+/// from `nodes/`.
+fn score_tier_graph(name: &str, artifact: &Path, value: i32) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("source.wat"),
+        format!(r#"(module (func (export "main") (param i32) (result i32) i32.const {value}))"#),
+    )
+    .unwrap();
+    let graph = serde_json::json!({
+        "nodes": [
+            { "id": "source", "wasm": "./source.wat", "outputs": [{ "name": "score", "type": "i32" }] },
+            {
+                "id": "scoreTier",
+                "wasm": artifact,
+                "inputs": [{ "name": "score", "type": "i32" }],
+                "outputs": [{ "name": "tier", "type": "string" }],
+                "sandbox": { "allow": ["log"] },
+                "config": { "gold": 12 }
+            }
+        ],
+        "edges": [{ "from": "source.score", "to": "scoreTier.score" }]
+    });
+    fs::write(dir.join("graph.json"), graph.to_string()).unwrap();
+    dir
+}
+
+#[test]
+fn sdk_component_nodes_build_and_run() {
+    let workspace = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../nodes");
+    let built = NodeBuild::new(workspace)
+        .with_nodes(vec!["scoreTier".to_string()])
+        .run()
+        .unwrap();
+    let artifact = &built[0].artifact;
+    let checked = check_node(artifact, None, Some(&[Capability::Log])).unwrap();
+    assert_eq!(checked.abi, Some(AbiVersion::Component));
+    assert!(checked.problems.is_empty(), "{:?}", checked.problems);
+
+    let dir = score_tier_graph("score-tier", artifact, 14);
+    let state = run_graph(dir.join("graph.json"));
+    fs::remove_dir_all(&dir).unwrap();
+    let state = state.unwrap();
+    assert_eq!(state["scoreTier"].abi, Some(AbiVersion::Component));
+    assert_eq!(
+        state["scoreTier"].outputs.get("tier"),
+        Some(&Payload::from("gold"))
+    );
+    assert_eq!(
+        state["scoreTier"].logs,
+        [LogLine {
+            level: LogLevel::Info,
+            message: "score 14 is gold".to_string(),
+        }]
+    );
+
+    let dir = score_tier_graph("score-tier-negative", artifact, -1);
+    let reported = run_graph(dir.join("graph.json")).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        matches!(&reported, Error::NodeReported { node, code: 2, message }
+            if node == "scoreTier" && message == "negative score"),
+        "{reported}"
+    );
+}
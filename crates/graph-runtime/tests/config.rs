@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::PathBuf;
+
+use graph_runtime::{check_node, run_graph, Error, Payload, Runtime};
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/config")
+        .join(path)
+}
+
+/// A directory holding a graph with one `configured.wat` node whose
+/// `config` is `config`, or none when it is empty.
+fn configured_graph(name: &str, wasm: &str, config: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let config = if config.is_empty() {
+        String::new()
+    } else {
+        format!(r#", "config": {config}"#)
+    };
+    let wasm = fixture(wasm);
+    fs::write(
+        dir.join("graph.json"),
+        format!(r#"{{ "nodes": [{{ "id": "node", "wasm": {wasm:?}, "pure": true{config} }}] }}"#),
+    )
+    .unwrap();
+    dir.join("graph.json")
+}
+
+#[test]
+fn init_receives_the_node_config_before_main() {
+    let state = run_graph(fixture("graph.json")).unwrap();
+    // `{"factor":3}` is 12 bytes; `init` is not called without a config, so
+    // the unconfigured node keeps its initial -1.
+    assert_eq!(state["configured"].output(), Some(&Payload::Int(12)));
+    assert_eq!(state["unconfigured"].output(), Some(&Payload::Int(-1)));
+    assert_eq!(
+        state["configured"].config,
+        Some(
+            serde_json::json!({ "factor": 3 })
+                .as_object()
+                .unwrap()
+                .clone()
+        )
+    );
+    assert_eq!(state["unconfigured"].config, None);
+
+    let checked = check_node(fixture("configured.wat"), None, None).unwrap();
+    assert!(checked.problems.is_empty(), "{:?}", checked.problems);
+}
+
+#[test]
+fn a_config_needs_an_init_export() {
+    let graph = configured_graph("config-ignored", "plain.wat", r#"{ "factor": 3 }"#);
+    let err = run_graph(&graph).unwrap_err();
+    fs::remove_dir_all(graph.parent().unwrap()).unwrap();
+    assert!(
+        matches!(&err, Error::ConfigIgnored(node) if node == "node"),
+        "{err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "Node node has a config, but its module does not export `init`."
+    );
+
+    let graph = configured_graph("config-string", "configured.wat", r#""fast""#);
+    let err = run_graph(&graph).unwrap_err();
+    fs::remove_dir_all(graph.parent().unwrap()).unwrap();
+    assert!(
+        err.to_string()
+            .contains(r#"Expected an object, found "fast"."#),
+        "{err}"
+    );
+}
+
+#[test]
+fn changing_the_config_invalidates_memoized_results() {
+    let runtime = Runtime::new();
+    let graph = configured_graph("config-memo", "configured.wat", r#"{ "a": 1 }"#);
+    let first = runtime.run_graph(&graph).unwrap();
+    assert!(!first["node"].cached);
+    assert!(runtime.run_graph(&graph).unwrap()["node"].cached);
+
+    let graph = configured_graph("config-memo", "configured.wat", r#"{ "a": 10 }"#);
+    let changed = runtime.run_graph(&graph).unwrap();
+    fs::remove_dir_all(graph.parent().unwrap()).unwrap();
+    assert!(!changed["node"].cached);
+    assert_eq!(changed["node"].output(), Some(&Payload::Int(8)));
+}
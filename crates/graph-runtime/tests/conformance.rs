@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use graph_runtime::{check_node, AbiVersion, Capability, Conformance, Error};
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+fn check_wat(name: &str, wat: &str, abi: Option<AbiVersion>) -> Conformance {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.wat", std::process::id()));
+    std::fs::write(&path, wat).unwrap();
+    let conformance = check_node(&path, abi, None);
+    std::fs::remove_file(&path).unwrap();
+    conformance.unwrap()
+}
+
+#[test]
+fn fixtures_conform_to_the_abi_their_exports_select() {
+    let echo = check_node(fixture("ports/echo.wat"), None, None).unwrap();
+    assert!(echo.is_ok(), "{:?}", echo.problems);
+    assert_eq!(echo.abi, Some(AbiVersion::V2));
+
+    let logger = check_node(fixture("log/logger.wat"), None, None).unwrap();
+    assert!(logger.is_ok(), "{:?}", logger.problems);
+    assert_eq!(logger.capabilities, vec![Capability::Log]);
+    let denied = check_node(fixture("log/logger.wat"), None, Some(&[])).unwrap();
+    assert_eq!(
+        denied.problems,
+        vec!["It imports graph.log, which needs the `log` capability."]
+    );
+
+    let silent = check_node(fixture("no-entry/silent.wat"), None, None).unwrap();
+    assert_eq!(silent.abi, None);
+    assert_eq!(
+        silent.problems,
+        vec!["It exports neither `run` (ABI v2) nor `main` (ABI v1)."]
+    );
+}
+
+#[test]
+fn signatures_and_memory_are_checked() {
+    let wrong_main = check_wat(
+        "wrong-main",
+        r#"(module (func (export "main") (param i64) (result i64) local.get 0))"#,
+        None,
+    );
+    assert_eq!(
+        wrong_main.problems,
+        vec!["`main` is a function (i64) -> i64, expected (i32) -> i32."]
+    );
+
+    let no_memory = check_wat(
+        "no-memory",
+        r#"(module
+          (func (export "alloc") (param i32) (result i32) i32.const 0)
+          (func (export "dealloc") (param i32 i32))
+          (func (export "run") (param i32 i32) (result i64) i64.const 0)
+          (func (export "error") (result i32) i32.const 0))"#,
+        None,
+    );
+    assert_eq!(
+        no_memory.problems,
+        vec![
+            "`error` is a function () -> i32, expected () -> i64.",
+            "It does not export `memory`, which ABI v2 and the `error` export need.",
+        ]
+    );
+
+    let declared = check_wat(
+        "declared",
+        r#"(module
+          (import "graph" "log" (func (param i32 i32)))
+          (memory (export "memory") 1)
+          (func (export "main") (param i32) (result i32) local.get 0))"#,
+        Some(AbiVersion::V2),
+    );
+    assert_eq!(declared.abi, Some(AbiVersion::V2));
+    assert_eq!(
+        declared.problems,
+        vec![
+            "It is declared as ABI v2, but the runtime would run it as ABI v1.",
+            "It does not export `run`, expected (i32, i32) -> i64.",
+            "It does not export `alloc`, expected (i32) -> i32.",
+            "It does not export `dealloc`, expected (i32, i32) -> ().",
+            "It imports graph.log as a function (i32, i32) -> (), expected (i32, i32, i32) -> ().",
+        ]
+    );
+}
+
+#[test]
+fn invalid_modules_are_errors() {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-invalid.wasm", std::process::id()));
+    std::fs::write(&path, b"\0asm\x02").unwrap();
+    let err = check_node(&path, None, None).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(err, Error::InvalidModule { .. }), "{err}");
+}
@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+use graph_runtime::{
+    export_graph, format_trace, load_graph, read_trace, ExportFormat, Runtime, TracedError,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+#[test]
+fn dot_shows_ports_and_labels_edges() {
+    let graph = load_graph(fixture("parallel")).unwrap();
+    let dot = export_graph(&graph, ExportFormat::Dot, None);
+    assert!(dot.starts_with("digraph G {\n"), "{dot}");
+    assert!(dot.contains(
+        r#""join" [label="join\n../ports/echo.wat\nin: left: i32, right: i32\nout: left: i32, right: i32"];"#
+    ), "{dot}");
+    assert!(
+        dot.contains(r#""left" -> "join" [label="out -> left"];"#),
+        "{dot}"
+    );
+    // Port-less nodes are fed by their last dependency.
+    assert!(dot.contains("\"root\" -> \"middle\";\n"), "{dot}");
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn mermaid_marks_ordering_only_dependencies() {
+    let json = r#"{ "nodes": [
+        { "id": "a", "wasm": "./a.wasm" },
+        { "id": "b", "wasm": "./b.wasm" },
+        { "id": "c/d", "wasm": "./c.wasm", "dependsOn": ["a", "b"] }
+    ] }"#;
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-export.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let graph = load_graph(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mermaid = export_graph(&graph, ExportFormat::Mermaid, None);
+    assert_eq!(
+        mermaid,
+        "flowchart LR\n    n0[\"a<br/>./a.wasm\"]\n    n1[\"b<br/>./b.wasm\"]\n    \
+         n2[\"c/d<br/>./c.wasm\"]\n    n0 -.-> n2\n    n1 --> n2\n"
+    );
+}
+
+#[test]
+fn traces_annotate_and_colour_nodes() {
+    let (state, failure) = Runtime::new().run_graph_partial(fixture("profile"));
+    assert!(failure.is_none());
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-export.jsonl", std::process::id()));
+    std::fs::write(&path, format_trace(&state, None)).unwrap();
+    let mut trace = read_trace(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(trace.nodes.len(), 3);
+
+    // Pretend the run failed on renderProfile instead.
+    trace.nodes.shift_remove("renderProfile");
+    trace.failure = Some(TracedError {
+        node: Some("renderProfile".to_string()),
+        kind: None,
+        code: None,
+        message: "Node renderProfile reported error 7: boom".to_string(),
+        inputs: Default::default(),
+        backtrace: Vec::new(),
+    });
+    let graph = load_graph(fixture("profile")).unwrap();
+    let dot = export_graph(&graph, ExportFormat::Dot, Some(&trace));
+    let line = |id: &str| {
+        dot.lines()
+            .find(|line| line.starts_with(&format!("    \"{id}\" [")))
+            .unwrap()
+            .to_string()
+    };
+    let fetch = line("fetchUser");
+    assert!(fetch.contains(r"\n{in=0} -> {out="), "{fetch}");
+    assert!(fetch.contains(" ms\""), "{fetch}");
+    assert!(!fetch.contains("fillcolor"), "{fetch}");
+    let render = line("renderProfile");
+    assert!(
+        render.contains(r"\nNode renderProfile reported error 7: boom"),
+        "{render}"
+    );
+    assert!(render.contains("fillcolor=\"#f8d7da\""), "{render}");
+
+    let mermaid = export_graph(&graph, ExportFormat::Mermaid, Some(&trace));
+    assert!(mermaid.contains("    class n2 failed\n"), "{mermaid}");
+}
+
+#[test]
+fn unreadable_traces_name_the_line() {
+    let path = std::env::temp_dir().join(format!("graph-runtime-{}-bad.jsonl", std::process::id()));
+    std::fs::write(
+        &path,
+        "{\"event\":\"failure\",\"message\":\"x\"}\nnot json\n",
+    )
+    .unwrap();
+    let err = read_trace(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(err.to_string().contains("bad.jsonl line 2: "), "{err}");
+}
@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use graph_runtime::{
+    load_graph, run_graph, Error, ExecutionState, FailureKind, GraphSpec, OnError, Outcome,
+    Payload, PortValues,
+};
+
+fn wasm(path: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path);
+    format!("{:?}", path.display().to_string())
+}
+
+/// `divide` runs as a root node, so it receives `0` and traps; `calcDiscount`
+/// consumes its output and `fetchUser` is independent of both.
+fn graph_json(policy: &str) -> String {
+    format!(
+        r#"{{ "nodes": [
+            {{ "id": "fetchUser", "wasm": {} }},
+            {{ "id": "divide", "wasm": {}, "onError": {policy} }},
+            {{ "id": "calcDiscount", "wasm": {}, "dependsOn": ["divide"] }}
+        ] }}"#,
+        wasm("profile/fetchUser.wat"),
+        wasm("failure/divide.wat"),
+        wasm("profile/calcDiscount.wat"),
+    )
+}
+
+fn with_graph<T>(name: &str, json: &str, f: impl FnOnce(&PathBuf) -> T) -> T {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let result = f(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+fn run_with(name: &str, policy: &str) -> graph_runtime::Result<ExecutionState> {
+    with_graph(name, &graph_json(policy), |path| run_graph(path))
+}
+
+fn load_with(name: &str, policy: &str) -> graph_runtime::Result<GraphSpec> {
+    with_graph(name, &graph_json(policy), |path| load_graph(path))
+}
+
+#[test]
+fn traps_are_reported_with_kind_inputs_and_backtrace() {
+    let err = run_with("trap-fail", r#""fail""#).unwrap_err();
+    let Error::Trap(failure) = &err else {
+        panic!("expected a trap, got {err}");
+    };
+    assert_eq!(failure.node, "divide");
+    assert_eq!(failure.kind, FailureKind::DivideByZero);
+    assert_eq!(
+        failure.inputs,
+        PortValues::from([("in".to_string(), Payload::Int(0))])
+    );
+    let functions: Vec<_> = failure
+        .backtrace
+        .iter()
+        .map(|frame| frame.function.as_deref())
+        .collect();
+    assert_eq!(functions, [Some("divide"), Some("main")]);
+
+    let rendered = err.to_string();
+    assert!(
+        rendered.starts_with(
+            "Node divide trapped: integer divide by zero with inputs {in=0}.\n    at divide @ 0x"
+        ),
+        "{rendered}"
+    );
+}
+
+#[test]
+fn skip_policy_skips_the_node_and_its_dependents() {
+    let state = run_with("trap-skip", r#""skip""#).unwrap();
+    assert_eq!(state["fetchUser"].outcome, Outcome::Succeeded);
+
+    let divide = &state["divide"];
+    assert_eq!(divide.outcome, Outcome::Skipped);
+    assert_eq!(divide.attempts, 1);
+    assert!(divide.outputs.is_empty());
+    assert_eq!(
+        divide.error.as_ref().map(|error| error.kind),
+        Some(FailureKind::DivideByZero)
+    );
+
+    let downstream = &state["calcDiscount"];
+    assert_eq!(downstream.outcome, Outcome::Skipped);
+    assert_eq!(downstream.attempts, 0);
+    assert_eq!(downstream.error, None);
+}
+
+#[test]
+fn skipped_nodes_do_not_load_their_modules() {
+    let broken =
+        std::env::temp_dir().join(format!("graph-runtime-{}-broken.wasm", std::process::id()));
+    std::fs::write(&broken, b"not a module").unwrap();
+    let json = graph_json(r#""skip""#).replace(
+        &wasm("profile/calcDiscount.wat"),
+        &format!("{:?}", broken.display().to_string()),
+    );
+    let state = with_graph("trap-skip-broken", &json, |path| run_graph(path));
+    std::fs::remove_file(&broken).unwrap();
+
+    let downstream = &state.unwrap()["calcDiscount"];
+    assert_eq!(downstream.outcome, Outcome::Skipped);
+    assert_eq!(downstream.abi, None);
+    assert!(downstream.wasm_hash.is_empty());
+}
+
+#[test]
+fn default_policy_substitutes_outputs() {
+    let state = run_with("trap-default", r#"{ "default": 42 }"#).unwrap();
+    assert_eq!(state["divide"].outcome, Outcome::Defaulted);
+    assert_eq!(state["divide"].output(), Some(&Payload::Int(42)));
+    assert_eq!(state["calcDiscount"].outcome, Outcome::Succeeded);
+    assert_eq!(state["calcDiscount"].output(), Some(&Payload::Int(7)));
+}
+
+#[test]
+fn retry_policy_fails_once_retries_run_out() {
+    assert_eq!(OnError::Retry(2).attempts(), 3);
+    let err = run_with("trap-retry", r#"{ "retry": 2 }"#).unwrap_err();
+    assert!(
+        matches!(&err, Error::Trap(failure) if failure.kind == FailureKind::DivideByZero),
+        "{err}"
+    );
+}
+
+#[test]
+fn invalid_policies_are_rejected() {
+    for (name, policy, expected) in [
+        (
+            "policy-unknown",
+            r#""ignore""#,
+            r#"Expected "fail", "skip", { "default": value } or { "retry": count }, found "ignore"."#,
+        ),
+        (
+            "policy-retry",
+            r#"{ "retry": 0 }"#,
+            "Expected a positive integer, found a number.",
+        ),
+        (
+            "policy-both",
+            r#"{ "default": 1, "retry": 2 }"#,
+            r#"Expected "fail", "skip", { "default": value } or { "retry": count }, found an object."#,
+        ),
+    ] {
+        let Err(Error::Invalid(report)) = load_with(name, policy) else {
+            panic!("expected {policy} to be rejected");
+        };
+        let errors: Vec<String> = report.iter().map(|d| d.error.to_string()).collect();
+        assert_eq!(errors, [expected], "{policy}");
+    }
+}
+
+#[test]
+fn reported_errors_are_distinct_from_traps() {
+    let json = |policy: &str| {
+        format!(
+            r#"{{ "nodes": [
+                {{ "id": "fetchUser", "wasm": {} }},
+                {{ "id": "found", "wasm": {lookup}, "dependsOn": ["fetchUser"] }},
+                {{ "id": "missing", "wasm": {lookup}, "onError": {policy} }}
+            ] }}"#,
+            wasm("profile/fetchUser.wat"),
+            lookup = wasm("failure/lookup.wat"),
+        )
+    };
+
+    let err = with_graph("reported-fail", &json(r#""fail""#), |path| run_graph(path)).unwrap_err();
+    assert!(
+        matches!(&err, Error::NodeReported { node, code: 404, message }
+            if node == "missing" && message == "user not found"),
+        "{err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "Node missing reported error 404: user not found"
+    );
+
+    let state = with_graph("reported-default", &json(r#"{ "default": -1 }"#), |path| {
+        run_graph(path)
+    })
+    .unwrap();
+    assert_eq!(state["found"].output(), Some(&Payload::Int(2001)));
+    assert_eq!(state["found"].error, None);
+    let missing = &state["missing"];
+    assert_eq!(missing.outcome, Outcome::Defaulted);
+    assert_eq!(missing.output(), Some(&Payload::Int(-1)));
+    let error = missing.error.as_ref().unwrap();
+    assert_eq!((error.kind, error.code), (FailureKind::Reported, Some(404)));
+    assert!(error.backtrace.is_empty());
+}
@@ -0,0 +1,24 @@
+;; ABI v2 node: prefixes its input with "Hello, ".
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (data (i32.const 0) "Hello, ")
+
+  (func $alloc (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+
+  (func (export "dealloc") (param i32 i32))
+
+  (func (export "run") (param $ptr i32) (param $len i32) (result i64)
+    (local $out i32)
+    (local $out_len i32)
+    (local.set $out_len (i32.add (local.get $len) (i32.const 7)))
+    (local.set $out (call $alloc (local.get $out_len)))
+    (memory.copy (local.get $out) (i32.const 0) (i32.const 7))
+    (memory.copy (i32.add (local.get $out) (i32.const 7)) (local.get $ptr) (local.get $len))
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $out)) (i64.const 32))
+      (i64.extend_i32_u (local.get $out_len)))))
@@ -0,0 +1,91 @@
+;; Core module implementing the graph:node/node world through the canonical
+;; ABI; tests wrap it into a component with `componentize`. `run` doubles the
+;; integer on its first input port into `out`, and reports error 7 for 0.
+(module
+  (import "$root" "log" (func $log (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (data (i32.const 16) "out")
+  (data (i32.const 32) "zero input")
+  (data (i32.const 48) "doubling")
+
+  ;; Bump allocator; the host never grows an allocation in place.
+  (func (export "cabi_realloc")
+    (param $old i32) (param $old_size i32) (param $align i32) (param $size i32)
+    (result i32)
+    (local $ptr i32)
+    global.get $heap
+    local.get $align
+    i32.add
+    i32.const 1
+    i32.sub
+    i32.const 0
+    local.get $align
+    i32.sub
+    i32.and
+    local.set $ptr
+    local.get $ptr
+    local.get $size
+    i32.add
+    global.set $heap
+    local.get $ptr)
+
+  ;; result<_, node-error> at 512: ok.
+  (func (export "init") (param i32 i32) (result i32)
+    i32.const 512
+    i32.const 0
+    i32.store8
+    i32.const 512)
+
+  ;; result<list<port>, node-error> at 544; the output port at 576.
+  (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+    (local $value i32)
+    local.get $ptr
+    i32.load offset=12
+    local.set $value
+    local.get $value
+    i32.eqz
+    if
+      i32.const 544
+      i32.const 1
+      i32.store8
+      i32.const 544
+      i32.const 7
+      i32.store offset=4
+      i32.const 544
+      i32.const 32
+      i32.store offset=8
+      i32.const 544
+      i32.const 10
+      i32.store offset=12
+      i32.const 544
+      return
+    end
+    i32.const 2
+    i32.const 48
+    i32.const 8
+    call $log
+    i32.const 576
+    i32.const 16
+    i32.store
+    i32.const 576
+    i32.const 3
+    i32.store offset=4
+    i32.const 576
+    i32.const 0
+    i32.store8 offset=8
+    i32.const 576
+    local.get $value
+    i32.const 2
+    i32.mul
+    i32.store offset=12
+    i32.const 544
+    i32.const 0
+    i32.store8
+    i32.const 544
+    i32.const 576
+    i32.store offset=4
+    i32.const 544
+    i32.const 1
+    i32.store offset=8
+    i32.const 544))
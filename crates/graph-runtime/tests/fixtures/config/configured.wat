@@ -0,0 +1,26 @@
+;; ABI v1 node with an `init` export: it remembers the length of the config
+;; text the host passes and `main` adds it to the input, so nodes sharing
+;; the module but not their config produce different outputs.
+(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (global $config_len (mut i32) (i32.const -1))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    global.get $heap
+    local.set $ptr
+    global.get $heap
+    local.get $len
+    i32.add
+    global.set $heap
+    local.get $ptr)
+
+  (func (export "init") (param $ptr i32) (param $len i32)
+    local.get $len
+    global.set $config_len)
+
+  (func (export "main") (param $input i32) (result i32)
+    local.get $input
+    global.get $config_len
+    i32.add))
@@ -0,0 +1,4 @@
+;; ABI v1 node without an `init` export, so it cannot take a config.
+(module
+  (func (export "main") (param i32) (result i32)
+    local.get 0))
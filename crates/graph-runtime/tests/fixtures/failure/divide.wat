@@ -0,0 +1,9 @@
+(module
+  ;; Divides 100 by its input, trapping when the input is zero.
+  (func $divide (param $n i32) (result i32)
+    i32.const 100
+    local.get $n
+    i32.div_s)
+  (func $main (export "main") (param $input i32) (result i32)
+    local.get $input
+    call $divide))
@@ -0,0 +1,17 @@
+(module
+  ;; Looks up a user id, reporting error 404 through the `error` export when
+  ;; the id is zero.
+  (memory (export "memory") 1)
+  (data (i32.const 16) "\94\01\00\00user not found")
+  (global $failed (mut i32) (i32.const 0))
+  (func (export "main") (param $id i32) (result i32)
+    (if (i32.eqz (local.get $id))
+      (then
+        (global.set $failed (i32.const 1))
+        (return (i32.const 0))))
+    (i32.add (local.get $id) (i32.const 1000)))
+  ;; The 18-byte record at offset 16, packed as `ptr << 32 | len`.
+  (func (export "error") (result i64)
+    (if (result i64) (global.get $failed)
+      (then (i64.const 0x1000000012))
+      (else (i64.const 0)))))
@@ -0,0 +1,6 @@
+(module
+  ;; Never returns, like a node whose panic handler is `loop {}`.
+  (func (export "main") (param i32) (result i32)
+    (loop $forever
+      (br $forever))
+    unreachable))
@@ -0,0 +1,10 @@
+(module
+  ;; Logs two lines through the host and returns its input plus one.
+  (import "graph" "log" (func $log (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "starting")
+  (data (i32.const 16) "input looks odd")
+  (func (export "main") (param $input i32) (result i32)
+    (call $log (i32.const 2) (i32.const 0) (i32.const 8))
+    (call $log (i32.const 3) (i32.const 16) (i32.const 15))
+    (i32.add (local.get $input) (i32.const 1))))
@@ -0,0 +1,11 @@
+(module
+  ;; Busy-loop for a while, then return the input plus one.
+  (func (export "main") (param $x i32) (result i32)
+    (local $i i32)
+    (local.set $i (i32.const 2000000))
+    (block $done
+      (loop $spin
+        (br_if $done (i32.eqz (local.get $i)))
+        (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+        (br $spin)))
+    (i32.add (local.get $x) (i32.const 1))))
@@ -0,0 +1,18 @@
+;; ABI v2 node that returns its input buffer untouched, so a ported node's
+;; output envelope mirrors the envelope it received.
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 16))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+
+  (func (export "dealloc") (param i32 i32))
+
+  (func (export "run") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len)))))
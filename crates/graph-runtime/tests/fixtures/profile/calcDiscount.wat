@@ -0,0 +1,7 @@
+(module
+  (func (export "main") (param $input i32) (result i32)
+    local.get $input
+    i32.const 5
+    i32.rem_s
+    i32.const 5
+    i32.add))
@@ -0,0 +1,3 @@
+(module
+  (func (export "main") (param i32) (result i32)
+    i32.const 1001))
@@ -0,0 +1,5 @@
+(module
+  (func (export "main") (param $input i32) (result i32)
+    local.get $input
+    i32.const 2
+    i32.mul))
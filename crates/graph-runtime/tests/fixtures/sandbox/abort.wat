@@ -0,0 +1,5 @@
+(module
+  ;; Imports a host function the runtime does not provide.
+  (import "env" "abort" (func $abort))
+  (func (export "main") (param i32) (result i32)
+    local.get 0))
@@ -0,0 +1,5 @@
+(module
+  ;; Asks for 32 pages (2 MiB) of linear memory up front.
+  (memory (export "memory") 32)
+  (func (export "main") (param i32) (result i32)
+    local.get 0))
@@ -0,0 +1,9 @@
+(module
+  ;; Fills eight bytes with WASI randomness and returns its input unchanged.
+  (import "wasi_snapshot_preview1" "random_get"
+    (func $random_get (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "main") (param i32) (result i32)
+    (call $random_get (i32.const 0) (i32.const 8))
+    local.get 0
+    i32.add))
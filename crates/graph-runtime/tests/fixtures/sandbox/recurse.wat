@@ -0,0 +1,11 @@
+(module
+  ;; Recurses 2000 frames deep before returning its input.
+  (func $down (param $depth i32) (result i32)
+    (if (result i32) (i32.eqz (local.get $depth))
+      (then (i32.const 0))
+      (else
+        (i32.add
+          (call $down (i32.sub (local.get $depth) (i32.const 1)))
+          (i32.const 0)))))
+  (func (export "main") (param i32) (result i32)
+    (i32.add (call $down (i32.const 2000)) (local.get 0))))
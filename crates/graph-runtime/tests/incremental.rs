@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use graph_runtime::{format_state, format_trace, read_trace, ExecutionState, Payload, Runtime};
+
+/// A scratch copy of the profile fixture whose modules the test can edit.
+fn scratch(name: &str) -> PathBuf {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/profile");
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for entry in fs::read_dir(fixture).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+    }
+    dir
+}
+
+/// Run the graph in `dir` against the trace of the previous run, if any,
+/// and store the new trace.
+fn run(dir: &Path) -> ExecutionState {
+    let trace_path = dir.join("run.jsonl");
+    let mut runtime = Runtime::new();
+    if trace_path.exists() {
+        runtime = runtime.with_previous_run(read_trace(&trace_path).unwrap());
+    }
+    let state = runtime.run_graph(dir.join("graph.json")).unwrap();
+    fs::write(&trace_path, format_trace(&state, None)).unwrap();
+    state
+}
+
+fn reused(state: &ExecutionState) -> Vec<&str> {
+    state
+        .iter()
+        .filter(|(_, record)| record.reused)
+        .map(|(id, _)| id.as_str())
+        .collect()
+}
+
+fn edit(dir: &Path, file: &str, from: &str, to: &str) {
+    let path = dir.join(file);
+    let source = fs::read_to_string(&path).unwrap();
+    assert!(source.contains(from));
+    fs::write(&path, source.replace(from, to)).unwrap();
+}
+
+#[test]
+fn only_changed_nodes_and_their_dependents_rerun() {
+    let dir = scratch("incremental");
+    let first = run(&dir);
+    assert!(reused(&first).is_empty());
+
+    let unchanged = run(&dir);
+    assert_eq!(
+        reused(&unchanged),
+        ["fetchUser", "calcDiscount", "renderProfile"]
+    );
+    assert_eq!(unchanged["renderProfile"].attempts, 0);
+    assert_eq!(
+        unchanged["renderProfile"].outputs,
+        first["renderProfile"].outputs
+    );
+    assert!(format_state(&unchanged).contains("[reused]"));
+
+    // calcDiscount now adds 6, so renderProfile receives a new discount.
+    edit(
+        &dir,
+        "calcDiscount.wat",
+        "i32.const 5\n    i32.add",
+        "i32.const 6\n    i32.add",
+    );
+    let changed = run(&dir);
+    assert_eq!(reused(&changed), ["fetchUser"]);
+    assert_eq!(changed["calcDiscount"].attempts, 1);
+    assert_ne!(
+        changed["renderProfile"].outputs,
+        first["renderProfile"].outputs
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dependents_of_a_change_with_the_same_outputs_are_reused() {
+    let dir = scratch("incremental-cutoff");
+    run(&dir);
+    edit(&dir, "calcDiscount.wat", "i32.add", "i32.add\n    nop");
+    let state = run(&dir);
+    assert_eq!(reused(&state), ["fetchUser", "renderProfile"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rewired_nodes_rerun() {
+    let dir = scratch("incremental-rewired");
+    let ports = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ports");
+    fs::copy(ports.join("echo.wat"), dir.join("echo.wat")).unwrap();
+    let graph = fs::read_to_string(ports.join("graph.json")).unwrap();
+    fs::write(dir.join("graph.json"), graph.replace("../profile/", "./")).unwrap();
+    let first = run(&dir);
+    assert_eq!(first["summary"].outputs["user"], Payload::Int(1001));
+
+    // Swap the ports the two edges into summary feed.
+    edit(&dir, "graph.json", "summary.user", "summary.tmp");
+    edit(&dir, "graph.json", "summary.discount", "summary.user");
+    edit(&dir, "graph.json", "summary.tmp", "summary.discount");
+    let rewired = run(&dir);
+    assert_eq!(reused(&rewired), ["fetchUser", "calcDiscount"]);
+    assert_eq!(rewired["summary"].outputs["user"], Payload::Int(6));
+    assert_eq!(rewired["summary"].outputs["discount"], Payload::Int(1001));
+
+    // Declaring another port changes the node even though nothing feeds it.
+    edit(
+        &dir,
+        "graph.json",
+        r#""required": false }"#,
+        r#""required": false },
+        { "name": "extra", "type": "i32", "required": false }"#,
+    );
+    let redeclared = run(&dir);
+    assert_eq!(reused(&redeclared), ["fetchUser", "calcDiscount"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
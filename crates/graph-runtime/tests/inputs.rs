@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+
+use graph_runtime::{
+    format_trace, graph_outputs, load_graph, read_trace, Error, GraphSpec, Payload, PortValues,
+    Runtime,
+};
+use serde_json::{json, Map, Value};
+
+fn fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inputs/graph.json")
+}
+
+fn inputs(value: Value) -> Map<String, Value> {
+    value.as_object().unwrap().clone()
+}
+
+/// Load a graph from an inline JSON document.
+fn load_inline(name: &str, json: &str) -> graph_runtime::Result<GraphSpec> {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let result = load_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+/// The problems reported for an invalid graph, with their JSON pointers.
+fn problems(result: graph_runtime::Result<GraphSpec>) -> Vec<(String, String)> {
+    match result {
+        Err(Error::Invalid(report)) => report
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.pointer, d.error.to_string()))
+            .collect(),
+        other => panic!("expected a validation report, got {other:?}"),
+    }
+}
+
+const NODE: &str = r#"{ "id": "a", "wasm": "./a.wasm",
+    "inputs": [{ "name": "id", "type": "i32" }], "outputs": [{ "name": "out", "type": "i32" }] }"#;
+
+#[test]
+fn graph_inputs_feed_node_ports_and_outputs_name_results() {
+    let outputs = Runtime::new()
+        .call(fixture(), inputs(json!({ "userId": 42 })))
+        .unwrap();
+    assert_eq!(
+        outputs,
+        PortValues::from([
+            ("user".to_string(), Payload::Int(42)),
+            ("discount".to_string(), Payload::Int(7)),
+        ])
+    );
+
+    let state = Runtime::new()
+        .with_inputs(inputs(json!({ "userId": 1001, "note": "vip" })))
+        .run_graph(fixture())
+        .unwrap();
+    assert_eq!(state["calcDiscount"].inputs["userId"], Payload::Int(1001));
+    assert_eq!(state["summary"].inputs["note"], Payload::from("vip"));
+    assert_eq!(state["summary"].dependencies, ["calcDiscount"]);
+    let graph = load_graph(fixture()).unwrap();
+    assert_eq!(graph_outputs(&graph, &state)["discount"], Payload::Int(6));
+}
+
+#[test]
+fn supplied_inputs_are_checked_against_the_declaration() {
+    let err = Runtime::new().call(fixture(), Map::new()).unwrap_err();
+    assert!(
+        matches!(&err, Error::MissingGraphInput(name) if name == "userId"),
+        "{err}"
+    );
+
+    let err = Runtime::new()
+        .call(fixture(), inputs(json!({ "userId": "42" })))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"Graph input userId expects i32 but received "42"."#
+    );
+
+    let err = Runtime::new()
+        .call(fixture(), inputs(json!({ "userId": 42, "userid": 1 })))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Graph has no input named userid.");
+}
+
+#[test]
+fn graph_inputs_and_outputs_are_validated_at_load_time() {
+    let json = format!(
+        r#"{{ "nodes": [{NODE}],
+  "inputs": [
+    {{ "name": "id", "type": "string", "to": ["a.id"] }},
+    {{ "name": "id", "type": "i32", "to": ["a.id", "b.id"], "default": 1 }}
+  ],
+  "outputs": [{{ "name": "out", "from": "a.missing" }}]
+}}"#
+    );
+    assert_eq!(
+        problems(load_inline("graph-io", &json)),
+        [
+            (
+                "/inputs/0/to/0".to_string(),
+                "Graph input id is string, but feeds the i32 input a.id.".to_string()
+            ),
+            (
+                "/inputs/1/name".to_string(),
+                "Duplicate graph input detected: id".to_string()
+            ),
+            (
+                "/inputs/1/to/0".to_string(),
+                "Input port a.id has more than one incoming edge.".to_string()
+            ),
+            (
+                "/inputs/1/to/1".to_string(),
+                "Graph input id refers to b.id, which is not an input port in the graph."
+                    .to_string()
+            ),
+            (
+                "/outputs/0/from".to_string(),
+                "Graph output out refers to a.missing, which is not an output port in the graph."
+                    .to_string()
+            ),
+        ]
+    );
+
+    let json = format!(
+        r#"{{ "nodes": [{NODE}], "inputs": [{{ "name": "id", "type": "i32", "to": ["a.id"], "default": "x" }}] }}"#
+    );
+    assert_eq!(
+        problems(load_inline("graph-io-default", &json)),
+        [(
+            "/inputs/0/default".to_string(),
+            r#"Graph input id expects i32 but received "x"."#.to_string()
+        )]
+    );
+
+    // A graph input connects a required port like an edge does.
+    let json = format!(
+        r#"{{ "nodes": [{NODE}], "inputs": [{{ "name": "id", "type": "i32", "to": ["a.id"] }}] }}"#
+    );
+    assert!(load_inline("graph-io-connected", &json).is_ok());
+}
+
+#[test]
+fn incremental_runs_rerun_nodes_whose_graph_inputs_changed() {
+    let trace = std::env::temp_dir().join(format!(
+        "graph-runtime-{}-inputs-trace.jsonl",
+        std::process::id()
+    ));
+    let state = Runtime::new()
+        .with_inputs(inputs(json!({ "userId": 42 })))
+        .run_graph(fixture())
+        .unwrap();
+    std::fs::write(&trace, format_trace(&state, None)).unwrap();
+    let previous = read_trace(&trace).unwrap();
+    std::fs::remove_file(&trace).unwrap();
+
+    let same = Runtime::new()
+        .with_previous_run(previous.clone())
+        .with_inputs(inputs(json!({ "userId": 42 })))
+        .run_graph(fixture())
+        .unwrap();
+    assert!(same["calcDiscount"].reused && same["summary"].reused);
+
+    let changed = Runtime::new()
+        .with_previous_run(previous)
+        .with_inputs(inputs(json!({ "userId": 43 })))
+        .run_graph(fixture())
+        .unwrap();
+    assert!(!changed["calcDiscount"].reused && !changed["summary"].reused);
+    assert_eq!(changed["summary"].outputs["user"], Payload::Int(43));
+}
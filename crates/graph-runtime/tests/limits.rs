@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use graph_runtime::{load_graph, Error, Limits, Runtime};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+#[test]
+fn node_limits_override_graph_limits() {
+    let graph = load_graph(fixture("limits")).unwrap();
+    let defaults = Limits {
+        fuel: Some(5),
+        timeout_ms: Some(10_000),
+    };
+    assert_eq!(
+        Limits::for_node(&graph, &graph.nodes[0], defaults),
+        Limits {
+            fuel: Some(1000),
+            timeout_ms: Some(200)
+        }
+    );
+    assert_eq!(
+        Limits::for_node(&graph, &graph.nodes[1], defaults),
+        Limits {
+            fuel: Some(5),
+            timeout_ms: Some(200)
+        }
+    );
+}
+
+#[test]
+fn hanging_node_times_out() {
+    let started = Instant::now();
+    let err = Runtime::new().run_graph(fixture("limits")).unwrap_err();
+    assert!(
+        matches!(&err, Error::NodeTimedOut { node, timeout }
+            if node == "stuck" && *timeout == Duration::from_millis(200)),
+        "{err}"
+    );
+    assert_eq!(err.to_string(), "Node stuck timed out after 200ms.");
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn hanging_node_runs_out_of_fuel() {
+    let runtime = Runtime::new().with_limits(Limits {
+        fuel: Some(100_000),
+        timeout_ms: None,
+    });
+    let err = runtime.run_graph(fixture("limits")).unwrap_err();
+    assert!(
+        matches!(&err, Error::FuelExhausted { node, fuel } if node == "stuck" && *fuel == 100_000),
+        "{err}"
+    );
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use graph_runtime::{run_graph, Capability, Error, LogLevel, LogLine, Payload};
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/log")
+}
+
+#[test]
+fn log_lines_are_recorded_per_node() {
+    let state = run_graph(fixture_dir().join("graph.json")).unwrap();
+    let expected = [
+        LogLine {
+            level: LogLevel::Info,
+            message: "starting".to_string(),
+        },
+        LogLine {
+            level: LogLevel::Warn,
+            message: "input looks odd".to_string(),
+        },
+    ];
+    assert_eq!(state["first"].logs, expected);
+    assert_eq!(state["second"].logs, expected);
+    assert_eq!(state["second"].output(), Some(&Payload::Int(2)));
+    assert_eq!(expected[1].to_string(), "warn: input looks odd");
+}
+
+#[test]
+fn logging_requires_the_log_capability() {
+    let wasm = fixture_dir().join("logger.wat");
+    let json = format!(
+        r#"{{ "nodes": [{{ "id": "muted", "wasm": {:?} }}] }}"#,
+        wasm.display().to_string()
+    );
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-muted.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let result = run_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let err = result.unwrap_err();
+    assert!(
+        matches!(&err, Error::ImportDenied { node, capability: Capability::Log, .. }
+            if node == "muted"),
+        "{err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "Node muted imports graph.log, but its sandbox policy does not allow `log`."
+    );
+}
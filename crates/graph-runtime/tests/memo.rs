@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use graph_runtime::{format_state, MemoStats, Runtime};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn cached(state: &graph_runtime::ExecutionState) -> Vec<&str> {
+    state
+        .iter()
+        .filter(|(_, record)| record.cached)
+        .map(|(id, _)| id.as_str())
+        .collect()
+}
+
+#[test]
+fn pure_nodes_are_served_from_the_result_cache() {
+    let runtime = Runtime::new();
+    let first = runtime.run_graph(fixture("memo")).unwrap();
+    assert!(cached(&first).is_empty());
+    assert_eq!(runtime.memo_stats(), MemoStats { hits: 0, stored: 2 });
+
+    let second = runtime.run_graph(fixture("memo")).unwrap();
+    assert_eq!(cached(&second), ["calcDiscount", "renderProfile"]);
+    assert_eq!(second["renderProfile"].attempts, 0);
+    for (id, record) in &first {
+        assert_eq!(second[id].outputs, record.outputs, "{id}");
+    }
+    assert!(format_state(&second).contains("[cached]"));
+    assert_eq!(runtime.memo_stats(), MemoStats { hits: 2, stored: 2 });
+
+    // Nodes that are not marked pure always run.
+    let profile = runtime.run_graph(fixture("profile")).unwrap();
+    assert!(cached(&profile).is_empty());
+
+    let uncached = Runtime::new().without_cache();
+    uncached.run_graph(fixture("memo")).unwrap();
+    assert!(cached(&uncached.run_graph(fixture("memo")).unwrap()).is_empty());
+    assert_eq!(uncached.memo_stats(), MemoStats::default());
+}
+
+#[test]
+fn results_persist_in_the_disk_cache() {
+    let dir = cache_dir("memo");
+    Runtime::new()
+        .with_disk_cache(&dir)
+        .run_graph(fixture("memo"))
+        .unwrap();
+    assert_eq!(std::fs::read_dir(dir.join("results")).unwrap().count(), 2);
+
+    let runtime = Runtime::new().with_disk_cache(&dir);
+    let state = runtime.run_graph(fixture("memo")).unwrap();
+    assert_eq!(cached(&state), ["calcDiscount", "renderProfile"]);
+    assert_eq!(runtime.memo_stats().hits, 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn concurrent_stores_of_one_entry_leave_it_whole() {
+    let dir = cache_dir("memo-concurrent");
+    let wasm = fixture("profile").with_file_name("calcDiscount.wat");
+    let nodes: Vec<_> = (0..8)
+        .map(|i| serde_json::json!({ "id": format!("calc{i}"), "wasm": wasm, "pure": true }))
+        .collect();
+    let graph = dir.with_extension("json");
+    std::fs::write(&graph, serde_json::json!({ "nodes": nodes }).to_string()).unwrap();
+
+    let runtime = Runtime::new().with_jobs(8).with_disk_cache(&dir);
+    let state = runtime.run_graph(&graph).unwrap();
+    std::fs::remove_file(&graph).unwrap();
+    assert_eq!(state.len(), 8);
+    let entries: Vec<_> = std::fs::read_dir(dir.join("results"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(entries.len(), 1, "{entries:?}");
+    assert!(entries[0].ends_with(".json"), "{entries:?}");
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use graph_runtime::{format_state, load_graph, run_graph, Error, GraphSpec, Payload};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+/// Load a graph from an inline JSON document.
+fn load_inline(name: &str, json: &str) -> graph_runtime::Result<GraphSpec> {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let result = load_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+/// The problems reported for an invalid graph, in source order.
+fn problems(result: graph_runtime::Result<GraphSpec>) -> Vec<Error> {
+    match result {
+        Err(Error::Invalid(report)) => report.diagnostics.into_iter().map(|d| d.error).collect(),
+        other => panic!("expected a validation report, got {other:?}"),
+    }
+}
+
+const TWO_NODES: &str = r#"
+    { "id": "a", "wasm": "./a.wasm", "outputs": [{ "name": "value", "type": "i32" }] },
+    { "id": "b", "wasm": "./b.wasm",
+      "inputs": [{ "name": "value", "type": "string" }, { "name": "extra", "required": false }],
+      "outputs": [{ "name": "result" }] }
+"#;
+
+fn two_nodes(edges: &str) -> String {
+    format!(r#"{{ "nodes": [{TWO_NODES}], "edges": [{edges}] }}"#)
+}
+
+#[test]
+fn ported_nodes_receive_every_connected_input() {
+    let state = run_graph(fixture("ports")).unwrap();
+
+    let summary = &state["summary"];
+    assert_eq!(summary.inputs["user"], Payload::Int(1001));
+    assert_eq!(summary.inputs["discount"], Payload::Int(6));
+    assert!(!summary.inputs.contains_key("note"));
+    assert_eq!(summary.outputs["user"], Payload::Int(1001));
+    assert_eq!(summary.outputs["discount"], Payload::Int(6));
+    assert_eq!(summary.dependencies, ["fetchUser", "calcDiscount"]);
+
+    assert!(format_state(&state)
+        .contains("summary: inputs {user=1001, discount=6} -> outputs {user=1001, discount=6}"));
+}
+
+#[test]
+fn unconnected_required_port_is_rejected_at_load_time() {
+    let problems = problems(load_inline("unconnected", &two_nodes("")));
+    assert_eq!(
+        problems.iter().map(Error::to_string).collect::<Vec<_>>(),
+        ["Required input port b.value is not connected."]
+    );
+}
+
+#[test]
+fn edges_must_join_compatible_existing_ports() {
+    let mismatch = two_nodes(r#"{ "from": "a.value", "to": "b.value" }"#);
+    assert!(matches!(
+        problems(load_inline("mismatch", &mismatch)).as_slice(),
+        [Error::PortTypeMismatch { .. }]
+    ));
+
+    let unknown = two_nodes(r#"{ "from": "a.missing", "to": "b.extra" }"#);
+    assert!(problems(load_inline("unknown", &unknown))
+        .iter()
+        .any(|err| err.to_string() == "Edge endpoint a.missing is not an output port of node a."));
+
+    let duplicate = two_nodes(
+        r#"{ "from": "a.value", "to": "b.extra" }, { "from": "b.result", "to": "b.extra" }"#,
+    );
+    assert!(problems(load_inline("duplicate", &duplicate))
+        .iter()
+        .any(|err| matches!(err, Error::DuplicateEdge(port) if port.to_string() == "b.extra")));
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use graph_runtime::{load_graph, run_graph, Error, GraphSpec, ReportTemplate};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+/// Load a graph whose only node is `a`, with one output `x`, and whose
+/// `report` is `report`.
+fn with_report(name: &str, report: &str) -> graph_runtime::Result<GraphSpec> {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.json", std::process::id()));
+    let json = format!(
+        r#"{{ "nodes": [{{ "id": "a", "wasm": "./a.wasm", "outputs": [{{ "name": "x" }}] }}],
+  "report": {report:?} }}"#
+    );
+    std::fs::write(&path, json).unwrap();
+    let result = load_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+/// The single problem reported for an invalid graph.
+fn problem(result: graph_runtime::Result<GraphSpec>) -> (String, Error) {
+    match result {
+        Err(Error::Invalid(mut report)) if report.len() == 1 => {
+            let diagnostic = report.diagnostics.remove(0);
+            (diagnostic.pointer, diagnostic.error)
+        }
+        other => panic!("expected a single problem, got {other:?}"),
+    }
+}
+
+#[test]
+fn templates_render_text_outputs_verbatim() {
+    let state = run_graph(fixture("bytes")).unwrap();
+    let template: ReportTemplate = "{{fetchUser.out}}: {{ greet.out }}! ({{missing.out}})"
+        .parse()
+        .unwrap();
+    assert_eq!(template.render(&state), "1001: Hello, 1001! (???)");
+    assert_eq!(
+        template
+            .ports()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["fetchUser.out", "greet.out", "missing.out"]
+    );
+}
+
+#[test]
+fn template_errors_are_reported_at_validation_time() {
+    assert!(with_report("report-ok", "a is {{a.x}} {single braces}").is_ok());
+
+    let cases = [
+        ("Value {{a.x", "unclosed `{{` at character 7"),
+        ("Value {{a.x}} }}", "unmatched `}}` at character 15"),
+        (
+            "Value {{ a }}",
+            r#"`{{a}}` at character 7 is not a "node.port" reference"#,
+        ),
+    ];
+    for (report, reason) in cases {
+        let (pointer, error) = problem(with_report("report-syntax", report));
+        assert_eq!(pointer, "/report");
+        assert!(
+            matches!(&error, Error::InvalidTemplate(found) if found == reason),
+            "{error}"
+        );
+    }
+
+    let (pointer, error) = problem(with_report("report-port", "{{a.x}} {{a.y}}"));
+    assert_eq!(pointer, "/report");
+    assert_eq!(
+        error.to_string(),
+        "Report template refers to a.y, which is not an output port in the graph."
+    );
+}
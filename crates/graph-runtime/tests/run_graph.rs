@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use graph_runtime::{
+    find_cycles, format_report, format_state, load_graph, run_graph, topo_sort, AbiVersion, Error,
+    GraphSpec, NodeSpec, Payload,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+fn node(id: &str, depends_on: &[&str]) -> NodeSpec {
+    NodeSpec {
+        id: id.to_string(),
+        wasm: format!("./{id}.wasm"),
+        depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+        ..NodeSpec::default()
+    }
+}
+
+fn graph<const N: usize>(nodes: [NodeSpec; N]) -> GraphSpec {
+    GraphSpec {
+        nodes: nodes.into(),
+        ..GraphSpec::default()
+    }
+}
+
+#[test]
+fn runs_profile_pipeline_in_dependency_order() {
+    let graph = load_graph(fixture("profile")).unwrap();
+    let state = run_graph(fixture("profile")).unwrap();
+
+    let order: Vec<&str> = state.keys().map(String::as_str).collect();
+    assert_eq!(order, ["fetchUser", "calcDiscount", "renderProfile"]);
+    assert_eq!(state["fetchUser"].output(), Some(&Payload::Int(1001)));
+    assert_eq!(state["calcDiscount"].input(), Some(&Payload::Int(1001)));
+    assert_eq!(state["calcDiscount"].output(), Some(&Payload::Int(6)));
+    assert_eq!(state["renderProfile"].output(), Some(&Payload::Int(12)));
+
+    assert_eq!(
+        format_state(&state),
+        "fetchUser: input=0 -> output=1001 (deps: none)\n\
+         calcDiscount: input=1001 -> output=6 (deps: fetchUser)\n\
+         renderProfile: input=6 -> output=12 (deps: calcDiscount)"
+    );
+    assert_eq!(
+        format_report(&graph, &state).as_deref(),
+        Some("User 1001 receives discount score 12.")
+    );
+}
+
+#[test]
+fn byte_nodes_receive_integers_as_decimal_text() {
+    let state = run_graph(fixture("bytes")).unwrap();
+
+    assert_eq!(state["fetchUser"].abi, Some(AbiVersion::V1));
+    assert_eq!(state["greet"].abi, Some(AbiVersion::V2));
+    assert_eq!(state["greet"].input(), Some(&Payload::from("1001")));
+    assert_eq!(
+        state["greet"].output().and_then(Payload::as_str),
+        Some("Hello, 1001")
+    );
+    assert!(format_state(&state).contains(r#"greet: input="1001" -> output="Hello, 1001""#));
+}
+
+#[test]
+fn integer_nodes_reject_non_numeric_bytes() {
+    let err = run_graph(fixture("bytes-mismatch")).unwrap_err();
+    assert!(
+        matches!(&err, Error::IncompatibleInput { node, .. } if node == "renderProfile"),
+        "{err}"
+    );
+}
+
+#[test]
+fn topo_sort_releases_ready_nodes_in_declaration_order() {
+    let nodes = graph([node("c", &["a", "b"]), node("b", &[]), node("a", &[])]);
+    let order: Vec<&str> = topo_sort(&nodes)
+        .unwrap()
+        .iter()
+        .map(|n| n.id.as_str())
+        .collect();
+    assert_eq!(order, ["b", "a", "c"]);
+}
+
+#[test]
+fn topo_sort_rejects_invalid_graphs() {
+    let duplicate = graph([node("a", &[]), node("a", &[])]);
+    assert!(matches!(topo_sort(&duplicate), Err(Error::DuplicateNode(id)) if id == "a"));
+
+    let dangling = graph([node("a", &["ghost"])]);
+    assert!(matches!(
+        topo_sort(&dangling),
+        Err(Error::UnknownDependency { node, dependency }) if node == "a" && dependency == "ghost"
+    ));
+
+    let cyclic = graph([node("a", &["b"]), node("b", &["a"])]);
+    let Err(Error::Cycle { cycles }) = topo_sort(&cyclic) else {
+        panic!("expected a cycle");
+    };
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].path(), ["a", "b", "a"]);
+}
+
+#[test]
+fn topo_sort_reports_every_cycle_as_an_edge_list() {
+    let mut cyclic = graph([
+        node("a", &["b"]),
+        node("b", &["a"]),
+        node("self", &["self"]),
+        node("d", &["f"]),
+        node("e", &["d"]),
+        node("f", &["e", "d"]),
+        node("ok", &["a"]),
+    ]);
+    cyclic.edges = vec![serde_json::from_str(r#"{ "from": "a.out", "to": "b.in" }"#).unwrap()];
+
+    let err = topo_sort(&cyclic).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Graph contains 3 cycles: a -> b -> a; self -> self; \
+         d -> f -> d (component: d, e, f); topological sort failed."
+    );
+
+    let Error::Cycle { cycles } = err else {
+        unreachable!()
+    };
+    let edges: Vec<(&str, &str, bool)> = cycles[0]
+        .edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str(), edge.via.is_some()))
+        .collect();
+    assert_eq!(edges, [("a", "b", true), ("b", "a", false)]);
+    assert_eq!(cycles[0].component, ["a", "b"]);
+    assert_eq!(cycles[2].component, ["d", "e", "f"]);
+    assert_eq!(find_cycles(&graph([node("a", &[])])), []);
+}
+
+#[test]
+fn cycles_are_found_past_long_dependency_chains() {
+    // The search walks the whole chain before reaching the cycle at its end.
+    const LENGTH: usize = 100_000;
+    let mut nodes = vec![node("n0", &[])];
+    for index in 1..LENGTH {
+        nodes.push(node(&format!("n{index}"), &[&format!("n{}", index - 1)]));
+    }
+    nodes.push(node("x", &["y", &format!("n{}", LENGTH - 1)]));
+    nodes.push(node("y", &["x"]));
+    let chain = GraphSpec {
+        nodes,
+        ..GraphSpec::default()
+    };
+
+    let cycles = find_cycles(&chain);
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].path(), ["x", "y", "x"]);
+    assert!(matches!(topo_sort(&chain), Err(Error::Cycle { .. })));
+}
+
+#[test]
+fn missing_entry_point_names_the_node() {
+    let err = run_graph(fixture("no-entry")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Node silent does not export a main function."
+    );
+}
+
+#[test]
+fn load_graph_requires_nodes_array() {
+    let graph = load_graph(fixture("profile")).unwrap();
+    assert_eq!(graph.nodes.len(), 3);
+
+    let path = std::env::temp_dir().join(format!(
+        "graph-runtime-{}-no-nodes.json",
+        std::process::id()
+    ));
+    std::fs::write(&path, r#"{ "node": [] }"#).unwrap();
+    let result = load_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+    let Err(Error::Invalid(report)) = result else {
+        panic!("expected a validation report, got {result:?}");
+    };
+    assert!(report
+        .iter()
+        .any(|diagnostic| matches!(diagnostic.error, Error::MissingNodes)));
+}
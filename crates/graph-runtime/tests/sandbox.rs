@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use graph_runtime::{
+    load_graph, run_graph, Capability, Error, ExecutionState, FailureKind, Payload, Sandbox,
+};
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sandbox")
+}
+
+/// Run a single node built from one of the sandbox fixtures under `policy`.
+fn run_node(name: &str, wasm: &str, policy: &str) -> graph_runtime::Result<ExecutionState> {
+    let wasm = fixture_dir().join(wasm);
+    let json = format!(
+        r#"{{ "nodes": [{{ "id": "{name}", "wasm": {:?}, "sandbox": {policy} }}] }}"#,
+        wasm.display().to_string()
+    );
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let result = run_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+#[test]
+fn node_policy_overrides_graph_policy() {
+    let graph = load_graph(fixture_dir().join("graph.json")).unwrap();
+    assert_eq!(
+        Sandbox::for_node(&graph, &graph.nodes[0]),
+        Sandbox {
+            max_memory_pages: Some(4),
+            allow: Some(vec![Capability::Random]),
+            ..Sandbox::default()
+        }
+    );
+    assert!(!Sandbox::for_node(&graph, &graph.nodes[1]).allows(Capability::Random));
+
+    let state = run_graph(fixture_dir().join("graph.json")).unwrap();
+    assert_eq!(state["recurse"].outputs["out"], Payload::Int(0));
+}
+
+#[test]
+fn imports_outside_the_policy_are_refused() {
+    let err = run_node("roll", "random.wat", "{}").unwrap_err();
+    assert!(
+        matches!(&err, Error::ImportDenied { node, capability: Capability::Random, .. }
+            if node == "roll"),
+        "{err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "Node roll imports wasi_snapshot_preview1.random_get, but its sandbox policy does not \
+         allow `random`."
+    );
+    run_node("roll", "random.wat", r#"{ "allow": ["random"] }"#).unwrap();
+
+    let err = run_node("abort", "abort.wat", r#"{ "allow": ["log", "clock"] }"#).unwrap_err();
+    assert!(
+        matches!(&err, Error::UnknownImport { import, .. } if import == "env.abort"),
+        "{err}"
+    );
+}
+
+#[test]
+fn resource_caps_are_enforced() {
+    let err = run_node("hungry", "hungry.wat", r#"{ "maxMemoryPages": 16 }"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Node hungry exceeded its sandbox limit of 16 memory pages (requested 32)."
+    );
+    run_node("hungry", "hungry.wat", r#"{ "maxMemoryPages": 32 }"#).unwrap();
+
+    let err = run_node("recurse", "recurse.wat", r#"{ "maxStackBytes": 4096 }"#).unwrap_err();
+    assert!(
+        matches!(&err, Error::Trap(failure)
+            if failure.node == "recurse" && failure.kind == FailureKind::StackOverflow),
+        "{err}"
+    );
+}
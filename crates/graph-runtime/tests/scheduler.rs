@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use graph_runtime::{ExecutionState, Payload, Runtime};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+fn run(jobs: usize) -> ExecutionState {
+    Runtime::new()
+        .with_jobs(jobs)
+        .run_graph(fixture("parallel"))
+        .unwrap()
+}
+
+#[test]
+fn results_do_not_depend_on_the_number_of_workers() {
+    let sequential = run(1);
+    for jobs in [2, 4, 16] {
+        let parallel = run(jobs);
+        let order: Vec<&str> = parallel.keys().map(String::as_str).collect();
+        assert_eq!(order, ["root", "left", "right", "middle", "join"]);
+        for (id, record) in &parallel {
+            assert_eq!(
+                record.inputs, sequential[id].inputs,
+                "{id} with {jobs} jobs"
+            );
+            assert_eq!(
+                record.outputs, sequential[id].outputs,
+                "{id} with {jobs} jobs"
+            );
+        }
+    }
+    assert_eq!(sequential["join"].outputs["left"], Payload::Int(2));
+    assert_eq!(sequential["join"].outputs["right"], Payload::Int(2));
+}
+
+#[test]
+fn timings_respect_dependencies_and_the_worker_limit() {
+    let state = run(2);
+    for record in state.values() {
+        assert!(record.timing.worker < 2);
+        assert!(record.timing.started <= record.timing.finished);
+        for dep in &record.dependencies {
+            assert!(state[dep].timing.finished <= record.timing.started);
+        }
+    }
+
+    // A single worker runs the nodes back to back.
+    let state = run(1);
+    let timings: Vec<_> = state.values().map(|record| record.timing).collect();
+    assert!(timings.iter().all(|timing| timing.worker == 0));
+    for pair in timings.windows(2) {
+        assert!(pair[0].finished <= pair[1].started);
+    }
+}
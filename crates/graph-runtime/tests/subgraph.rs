@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use graph_runtime::{format_report, load_graph, Error, GraphSpec, Payload, Runtime};
+use serde_json::json;
+
+fn fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/subgraph/graph.json")
+}
+
+fn profile() -> PathBuf {
+    fixture().with_file_name("profile/profile.graph.json")
+}
+
+/// A scratch directory for graphs that embed one another.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph-runtime-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The problems reported for an invalid graph, with the file and JSON
+/// pointer of each.
+fn problems(result: graph_runtime::Result<GraphSpec>) -> Vec<(PathBuf, String, String)> {
+    match result {
+        Err(Error::Invalid(report)) => report
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.file, d.pointer, d.error.to_string()))
+            .collect(),
+        other => panic!("expected a validation report, got {other:?}"),
+    }
+}
+
+fn write(path: &Path, json: serde_json::Value) {
+    fs::write(path, json.to_string()).unwrap();
+}
+
+#[test]
+fn subgraph_nodes_are_flattened_with_namespaced_ids() {
+    let graph = load_graph(fixture()).unwrap();
+    let ids: Vec<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(
+        ids,
+        [
+            "first/calcDiscount",
+            "first/renderProfile",
+            "second/calcDiscount",
+            "second/renderProfile",
+            "summary"
+        ]
+    );
+    assert_eq!(
+        graph.nodes[0].wasm,
+        "./profile/../../profile/calcDiscount.wat"
+    );
+    let inputs: Vec<&str> = graph
+        .inputs
+        .iter()
+        .map(|input| input.name.as_str())
+        .collect();
+    assert_eq!(inputs, ["userId", "second/userId"]);
+    assert_eq!(
+        graph.report.as_ref().unwrap().to_string(),
+        "Discounts {{first/calcDiscount.out}} and {{second/calcDiscount.out}}."
+    );
+
+    let runtime = Runtime::new().with_inputs(json!({ "userId": 42 }).as_object().unwrap().clone());
+    let state = runtime.run_graph(fixture()).unwrap();
+    assert_eq!(state["first/calcDiscount"].input(), Some(&Payload::Int(42)));
+    assert_eq!(
+        state["second/calcDiscount"].input(),
+        Some(&Payload::Int(1001))
+    );
+    assert_eq!(
+        state["first/renderProfile"].dependencies,
+        ["first/calcDiscount"]
+    );
+    assert_eq!(
+        state["summary"].dependencies,
+        ["first/renderProfile", "second/renderProfile"]
+    );
+    assert_eq!(state["summary"].outputs["first"], Payload::Int(14));
+    assert_eq!(state["summary"].outputs["second"], Payload::Int(12));
+    assert_eq!(
+        format_report(&graph, &state).as_deref(),
+        Some("Discounts 7 and 6.")
+    );
+
+    let outputs = runtime
+        .call(
+            fixture(),
+            json!({ "userId": 3 }).as_object().unwrap().clone(),
+        )
+        .unwrap();
+    assert_eq!(outputs["score"], Payload::Int(16));
+}
+
+#[test]
+fn subgraph_ports_are_wired_like_node_ports() {
+    let dir = scratch("subgraph-ports");
+    let profile = profile();
+    write(
+        &dir.join("graph.json"),
+        json!({
+            "nodes": [
+                { "id": "profile", "graph": profile, "wasm": "./x.wasm", "onError": "skip" }
+            ]
+        }),
+    );
+    let errors: Vec<String> = problems(load_graph(dir.join("graph.json")))
+        .into_iter()
+        .map(|(_, _, error)| error)
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "A subgraph node cannot set `onError`; set it on the nodes of the embedded graph.",
+            "Unknown key `wasm`."
+        ]
+    );
+
+    write(
+        &dir.join("graph.json"),
+        json!({
+            "nodes": [
+                { "id": "profile", "graph": profile },
+                { "id": "show", "wasm": "./show.wasm", "inputs": [{ "name": "text", "type": "string" }] },
+                { "id": "profile/calcDiscount", "wasm": "./calc.wasm" }
+            ],
+            "edges": [
+                { "from": "profile.nope", "to": "show.text" },
+                { "from": "profile.score", "to": "show.text" }
+            ]
+        }),
+    );
+    let errors: Vec<String> = problems(load_graph(dir.join("graph.json")))
+        .into_iter()
+        .map(|(_, pointer, error)| format!("{pointer}: {error}"))
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        errors,
+        [
+            "/edges/0/from: Edge endpoint profile.nope is not an output port of node profile.",
+            "/edges/1/to: Input port show.text has more than one incoming edge.",
+            "/nodes/0/id: Duplicate node id detected: profile/calcDiscount",
+        ]
+    );
+}
+
+#[test]
+fn subgraph_dependencies_and_limits_apply_to_the_embedded_nodes() {
+    let dir = scratch("subgraph-settings");
+    write(
+        &dir.join("graph.json"),
+        json!({
+            "nodes": [
+                { "id": "first", "wasm": fixture().with_file_name("../profile/fetchUser.wat") },
+                {
+                    "id": "profile",
+                    "graph": profile(),
+                    "dependsOn": ["first"],
+                    "limits": { "fuel": 1000, "timeoutMs": 50 }
+                }
+            ]
+        }),
+    );
+    let graph = load_graph(dir.join("graph.json")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    for node in &graph.nodes[1..] {
+        assert_eq!(node.depends_on, ["first"], "{}", node.id);
+        assert_eq!(node.limits.unwrap().fuel, Some(1000), "{}", node.id);
+    }
+}
+
+#[test]
+fn problems_in_embedded_graphs_are_reported_with_their_file() {
+    let dir = scratch("subgraph-problems");
+    let (outer, inner) = (dir.join("graph.json"), dir.join("inner.graph.json"));
+    write(
+        &inner,
+        json!({
+            "inputs": [{ "name": "x", "type": "i32", "to": ["node.in"] }],
+            "nodes": [{ "id": "node", "wasm": "./missing.wasm" }],
+            "outputs": [{ "name": "y", "from": "node.out" }]
+        }),
+    );
+    write(
+        &outer,
+        json!({ "nodes": [{ "id": "inner", "graph": "./inner.graph.json" }] }),
+    );
+    // The embedded graph's input has no default, so the port is required.
+    assert_eq!(
+        problems(load_graph(&outer)),
+        [(
+            outer.clone(),
+            "/nodes/0/inputs/0".to_string(),
+            "Required input port inner.x is not connected.".to_string()
+        )]
+    );
+
+    let checked = problems(graph_runtime::validate_graph(&outer));
+    assert!(checked
+        .iter()
+        .any(|(file, pointer, _)| file == &inner && pointer == "/nodes/0/wasm"));
+
+    write(
+        &inner,
+        json!({ "nodes": [{ "id": "again", "graph": "./graph.json" }] }),
+    );
+    let recursive = problems(load_graph(&outer));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(recursive.len(), 1);
+    assert_eq!(recursive[0].0, inner);
+    assert_eq!(recursive[0].1, "/nodes/0/graph");
+    assert!(
+        recursive[0].2.ends_with("graph.json embeds itself."),
+        "{recursive:?}"
+    );
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use graph_runtime::{format_chrome_trace, format_trace, Runtime};
+use serde_json::Value;
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn lines(trace: &str) -> Vec<Value> {
+    trace
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn trace_has_one_line_per_node() {
+    let (state, failure) = Runtime::new().run_graph_partial(fixtures().join("profile/graph.json"));
+    assert!(failure.is_none());
+    let events = lines(&format_trace(&state, None));
+    let nodes: Vec<_> = events.iter().map(|event| &event["node"]).collect();
+    assert_eq!(nodes, ["fetchUser", "calcDiscount", "renderProfile"]);
+
+    let fetch = &events[0];
+    assert_eq!(fetch["event"], "node");
+    assert_eq!(fetch["outcome"], "succeeded");
+    assert_eq!(fetch["attempts"], 1);
+    assert_eq!(fetch["inputs"], serde_json::json!({ "in": 0 }));
+    assert_eq!(fetch["wasmHash"].as_str().unwrap().len(), 64);
+    assert!(fetch["finishedUs"].as_u64() >= fetch["startedUs"].as_u64());
+    assert_eq!(events[1]["dependencies"], serde_json::json!(["fetchUser"]));
+}
+
+/// `divide` runs as a root node, so it receives `0` and traps; a single
+/// worker runs `fetchUser` first.
+#[test]
+fn failed_runs_keep_finished_nodes_and_the_failure() {
+    let json = format!(
+        r#"{{ "nodes": [
+            {{ "id": "fetchUser", "wasm": {:?} }},
+            {{ "id": "divide", "wasm": {:?} }}
+        ] }}"#,
+        fixtures()
+            .join("profile/fetchUser.wat")
+            .display()
+            .to_string(),
+        fixtures().join("failure/divide.wat").display().to_string(),
+    );
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-trace.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let (state, failure) = Runtime::new().with_jobs(1).run_graph_partial(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let failure = failure.expect("divide traps");
+    assert_eq!(state.keys().collect::<Vec<_>>(), ["fetchUser"]);
+    let events = lines(&format_trace(&state, Some(&failure)));
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1]["event"], "failure");
+    assert_eq!(events[1]["node"], "divide");
+    assert_eq!(events[1]["kind"], "divide-by-zero");
+    assert!(events[1]["backtrace"]
+        .as_array()
+        .is_some_and(|frames| !frames.is_empty()));
+
+    let chrome: Value = serde_json::from_str(&format_chrome_trace(&state, Some(&failure))).unwrap();
+    let phases: Vec<_> = chrome["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["ph"].as_str().unwrap())
+        .collect();
+    assert_eq!(phases, ["M", "X", "i"]);
+    assert_eq!(chrome["traceEvents"][1]["name"], "fetchUser");
+    assert_eq!(chrome["traceEvents"][2]["args"]["kind"], "divide-by-zero");
+}
@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use graph_runtime::{validate_graph, Error, GraphSpec, Position, ValidationReport, GRAPH_SCHEMA};
+use serde_json::Value;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .join("graph.json")
+}
+
+/// Validate an inline JSON document from the temp directory, where none of
+/// the referenced wasm files exist.
+fn validate_inline(name: &str, json: &str) -> graph_runtime::Result<GraphSpec> {
+    let path =
+        std::env::temp_dir().join(format!("graph-runtime-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let result = validate_graph(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+fn report(result: graph_runtime::Result<GraphSpec>) -> ValidationReport {
+    match result {
+        Err(Error::Invalid(report)) => report,
+        other => panic!("expected a validation report, got {other:?}"),
+    }
+}
+
+#[test]
+fn fixtures_validate_cleanly() {
+    for name in [
+        "profile", "bytes", "ports", "sandbox", "log", "memo", "config",
+    ] {
+        validate_graph(fixture(name)).unwrap();
+    }
+}
+
+#[test]
+fn reports_every_problem_with_its_position() {
+    let json = r#"{
+  "nodes": [
+    { "id": "a", "wasm": "./a.wasm", "dependOn": ["b"] },
+    { "id": "b", "wasm": 7 },
+    { "id": "c", "wasm": "./c.wasm", "outputs": [{ "name": "out", "type": "float" }] }
+  ],
+  "edges": [{ "from": "a", "to": "c.in" }],
+  "extra": true
+}"#;
+    let report = report(validate_inline("many", json));
+
+    let lines: Vec<(Position, String)> = report
+        .iter()
+        .filter(|d| !matches!(d.error, Error::MissingWasm { .. }))
+        .map(|d| (d.position, d.error.to_string()))
+        .collect();
+    let at = |line, column| Position { line, column };
+    assert_eq!(
+        lines,
+        [
+            (
+                at(3, 38),
+                "Unknown key `dependOn`. Did you mean `dependsOn`?".to_string()
+            ),
+            (at(4, 26), "Expected a string, found a number.".to_string()),
+            (
+                at(5, 75),
+                r#"Expected one of "any", "i32", "string", "json", "bytes", found "float"."#
+                    .to_string()
+            ),
+            (
+                at(7, 23),
+                r#"Expected a "node.port" reference, found "a"."#.to_string()
+            ),
+            (at(8, 3), "Unknown key `extra`.".to_string()),
+        ]
+    );
+    assert_eq!(report.diagnostics[2].pointer, "/nodes/1/wasm");
+
+    let rendered = report.to_string();
+    assert!(rendered.starts_with("graph specification has 6 problems:"));
+    assert!(rendered.contains("many.json:3:38: Unknown key `dependOn`."));
+}
+
+#[test]
+fn reports_cross_reference_problems_together() {
+    let json = r#"{
+  "nodes": [
+    { "id": "a", "wasm": "./a.wasm", "dependsOn": ["c"] },
+    { "id": "b", "wasm": "./b.wasm", "dependsOn": ["a", "ghost"] },
+    { "id": "c", "wasm": "./c.wasm", "dependsOn": ["b"] },
+    { "id": "a", "wasm": "./a.wasm" }
+  ]
+}"#;
+    let report = report(validate_inline("semantic", json));
+    let errors: Vec<String> = report.iter().map(|d| d.error.to_string()).collect();
+
+    assert!(errors.contains(&"Node b depends on unknown node ghost".to_string()));
+    assert!(errors.contains(&"Duplicate node id detected: a".to_string()));
+    assert!(errors.contains(
+        &"Graph contains a cycle: a -> b -> c -> a; topological sort failed.".to_string()
+    ));
+    assert!(report.iter().any(|d| matches!(
+        &d.error,
+        Error::MissingWasm { node, .. } if node == "b"
+    ) && d.pointer == "/nodes/1/wasm"));
+}
+
+#[test]
+fn schema_and_validator_agree_on_keys() {
+    let schema: Value = serde_json::from_str(GRAPH_SCHEMA).unwrap();
+    let keys = |pointer: &str| -> Vec<&str> {
+        let mut keys: Vec<&str> = schema
+            .pointer(pointer)
+            .and_then(Value::as_object)
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    };
+    assert_eq!(
+        keys("/properties"),
+        ["$schema", "edges", "inputs", "limits", "nodes", "outputs", "report", "sandbox"]
+    );
+    assert_eq!(
+        keys("/$defs/node/properties"),
+        [
+            "config",
+            "dependsOn",
+            "graph",
+            "id",
+            "inputs",
+            "limits",
+            "onError",
+            "outputs",
+            "pure",
+            "sandbox",
+            "wasm"
+        ]
+    );
+    assert_eq!(keys("/$defs/limits/properties"), ["fuel", "timeoutMs"]);
+    assert_eq!(
+        keys("/$defs/sandbox/properties"),
+        [
+            "allow",
+            "maxMemoryPages",
+            "maxStackBytes",
+            "maxTableElements",
+            "preopens"
+        ]
+    );
+    assert_eq!(
+        keys("/$defs/preopen/properties"),
+        ["guest", "host", "writable"]
+    );
+    assert_eq!(keys("/$defs/port/properties"), ["name", "required", "type"]);
+    assert_eq!(keys("/$defs/edge/properties"), ["from", "to"]);
+    assert_eq!(
+        keys("/$defs/graphInput/properties"),
+        ["default", "name", "to", "type"]
+    );
+    assert_eq!(keys("/$defs/graphOutput/properties"), ["from", "name"]);
+
+    // Every key the schema allows must get past the validator.
+    let json = r#"{
+  "$schema": "../../../schema/graph.schema.json",
+  "limits": { "fuel": 1000000, "timeoutMs": 500 },
+  "sandbox": { "maxMemoryPages": 16, "maxTableElements": 100, "maxStackBytes": 65536 },
+  "nodes": [
+    { "id": "a", "wasm": "./a.wasm", "inputs": [{ "name": "seed", "type": "i32" }],
+      "outputs": [{ "name": "x", "type": "i32", "required": true }],
+      "onError": { "default": 7 }, "pure": true },
+    { "id": "b", "wasm": "./b.wasm", "dependsOn": ["a"], "inputs": [{ "name": "x" }],
+      "limits": { "fuel": 10 }, "onError": { "retry": 2 },
+      "sandbox": { "allow": ["clock", "fs"],
+                   "preopens": [{ "host": "./data", "guest": "/data", "writable": false }] } }
+  ],
+  "edges": [{ "from": "a.x", "to": "b.x" }],
+  "inputs": [{ "name": "seed", "type": "i32", "to": ["a.seed"], "default": 1 }],
+  "outputs": [{ "name": "result", "from": "a.x" }],
+  "report": "a produced {{a.x}}"
+}"#;
+    let report = report(validate_inline("schema-keys", json));
+    assert!(report
+        .iter()
+        .all(|d| matches!(d.error, Error::MissingWasm { .. })));
+}
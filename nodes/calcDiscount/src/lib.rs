@@ -0,0 +1,15 @@
+#![no_std]
+
+use graph_node_sdk::{config, graph_node, node_log};
+
+/// Derives a pseudo-discount from the upstream user identifier:
+/// `user_id % modulus + base`, both taken from the node's `config`.
+#[graph_node(config)]
+fn calc_discount(user_id: i32) -> i32 {
+    let config = config();
+    let modulus = config.i32("modulus").filter(|&m| m != 0).unwrap_or(5);
+    let base = config.i32("base").unwrap_or(5);
+    let discount = user_id % modulus + base;
+    node_log!(debug, "user {user_id} gets discount {discount}");
+    discount
+}
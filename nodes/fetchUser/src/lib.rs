@@ -0,0 +1,9 @@
+#![no_std]
+
+use graph_node_sdk::graph_node;
+
+/// Looks up the user the graph was called for and yields their identifier.
+#[graph_node]
+fn fetch_user(user_id: i32) -> i32 {
+    user_id
+}
@@ -0,0 +1,9 @@
+#![no_std]
+
+use graph_node_sdk::graph_node;
+
+/// Doubles the discount as the final presentation metric.
+#[graph_node]
+fn render_profile(discount: i32) -> i32 {
+    discount * 2
+}
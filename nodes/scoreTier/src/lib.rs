@@ -0,0 +1,23 @@
+#![no_std]
+
+use graph_node_sdk::{config, graph_node, node_log, NodeError, Ports};
+
+/// Names the tier a profile score falls in: `gold` from the `gold` threshold
+/// in the node's `config` up, `silver` below it and `bronze` for zero.
+#[graph_node(component)]
+fn score_tier(inputs: Ports) -> Result<Ports, NodeError> {
+    let score = inputs
+        .i32("score")
+        .ok_or_else(|| NodeError::new(1, "missing score"))?;
+    if score < 0 {
+        return Err(NodeError::new(2, "negative score"));
+    }
+    let gold = config().i32("gold").unwrap_or(10);
+    let tier = match score {
+        0 => "bronze",
+        score if score >= gold => "gold",
+        _ => "silver",
+    };
+    node_log!(info, "score {score} is {tier}");
+    Ok(Ports::new().set("tier", tier))
+}
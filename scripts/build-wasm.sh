@@ -6,17 +6,15 @@ TARGET="wasm32-wasip1"
 if ! rustup target list --installed | grep -q "^${TARGET}$"; then
   echo "Installing Rust target ${TARGET}..." >&2
   if ! rustup target add "${TARGET}"; then
-    cat >&2 <<'EOF'
+    cat >&2 <<'MSG'
 Failed to install the required Rust target.
 If the environment blocks network access, install the target ahead of time or
 configure rustup with an offline mirror before running this script.
-EOF
+MSG
     exit 1
   fi
 fi
 
-for src in nodes/*.rs; do
-  name="$(basename "${src}" .rs)"
-  echo "Compiling ${src} -> nodes/${name}.wasm" >&2
-  rustc --target "${TARGET}" "${src}" -O -o "nodes/${name}.wasm"
-done
+# `graph build` compiles the node packages in nodes/, copies the changed
+# artifacts to nodes/<id>.wasm and records them in nodes/build-manifest.json.
+exec cargo run --quiet -p graph-runtime --bin graph -- build "$@"